@@ -0,0 +1,98 @@
+// Contents of `.riscv.attributes`, the build attributes linkers merge and
+// `readelf -A` prints.
+
+use std::collections::BTreeMap;
+
+pub const TAG_RISCV_STACK_ALIGN: u64 = 4;
+pub const TAG_RISCV_ARCH: u64 = 5;
+pub const TAG_RISCV_UNALIGNED_ACCESS: u64 = 6;
+pub const TAG_RISCV_PRIV_SPEC: u64 = 8;
+pub const TAG_RISCV_PRIV_SPEC_MINOR: u64 = 10;
+pub const TAG_RISCV_PRIV_SPEC_REVISION: u64 = 12;
+pub const TAG_RISCV_ATOMIC_ABI: u64 = 14;
+pub const TAG_RISCV_X3_REG_USAGE: u64 = 16;
+
+/// Attributes that apply to the whole object file.
+const TAG_FILE: u8 = 1;
+
+#[derive(Debug, Clone)]
+pub enum AttributeValue {
+    Number(u64),
+    Text(String),
+}
+
+/// Tag of an attribute name in `.attribute`, `arch` or `stack_align`.
+pub fn attribute_tag(name: &str) -> Option<u64> {
+    let tag = match name.strip_prefix("Tag_RISCV_").unwrap_or(name) {
+        "stack_align" => TAG_RISCV_STACK_ALIGN,
+        "arch" => TAG_RISCV_ARCH,
+        "unaligned_access" => TAG_RISCV_UNALIGNED_ACCESS,
+        "priv_spec" => TAG_RISCV_PRIV_SPEC,
+        "priv_spec_minor" => TAG_RISCV_PRIV_SPEC_MINOR,
+        "priv_spec_revision" => TAG_RISCV_PRIV_SPEC_REVISION,
+        "atomic_abi" => TAG_RISCV_ATOMIC_ABI,
+        "x3_reg_usage" => TAG_RISCV_X3_REG_USAGE,
+        _ => return None,
+    };
+
+    Some(tag)
+}
+
+/// Whether the tag holds a string, odd tags do and even ones hold a number.
+pub fn is_text_tag(tag: u64) -> bool {
+    tag % 2 == 1
+}
+
+#[derive(Debug, Default)]
+pub struct Attributes {
+    values: BTreeMap<u64, AttributeValue>,
+}
+
+impl Attributes {
+    pub fn set(&mut self, tag: u64, value: AttributeValue) {
+        self.values.insert(tag, value);
+    }
+    /// Section contents, a single `riscv` vendor subsection with the file attributes.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut attributes = Vec::new();
+
+        for (tag, value) in &self.values {
+            uleb128(*tag, &mut attributes);
+
+            match value {
+                AttributeValue::Number(n) => uleb128(*n, &mut attributes),
+                AttributeValue::Text(text) => {
+                    attributes.extend(text.as_bytes());
+                    attributes.push(0);
+                }
+            }
+        }
+
+        let vendor = b"riscv\0";
+        let file_len = 1 + 4 + attributes.len();
+        let subsection_len = 4 + vendor.len() + file_len;
+
+        let mut content = vec![b'A'];
+        content.extend((subsection_len as u32).to_le_bytes());
+        content.extend(vendor);
+        content.push(TAG_FILE);
+        content.extend((file_len as u32).to_le_bytes());
+        content.extend(attributes);
+
+        content
+    }
+}
+
+fn uleb128(mut value: u64, out: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+
+        out.push(byte | 0x80);
+    }
+}
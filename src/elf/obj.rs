@@ -1,33 +1,39 @@
 use std::{collections::HashMap, fs::File, io::Write, path::Path};
 
 use object::{
-    Architecture, BinaryFormat, Endianness, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+    Architecture, BinaryFormat, Endianness, FileFlags, SectionKind, SymbolFlags, SymbolKind,
+    SymbolScope,
+    elf::SHT_RISCV_ATTRIBUTES,
     write::{Object, Relocation, SectionId, Symbol, SymbolId, SymbolSection},
 };
 use thiserror::Error;
 
+use super::attributes::Attributes;
 use crate::parser::ast::{SymbolInfo, Visibility};
 
 pub struct Elf<'a> {
     pub elf: Object<'a>,
     pub values: HashMap<SectionId, u64>,
+    pub sections: HashMap<String, SectionId>,
     pub symbols: HashMap<String, SymbolData>
 }
 
-pub struct CreateSectionInfo {
-    pub name: &'static str,
-    pub content: &'static [u8],
+pub struct CreateSymbolInfo<'b> {
+    pub name: &'b str,
+    pub size: u64,
     pub section_id: SectionId,
     pub symbol_kind: SymbolKind,
-    pub symbol_info: &'static SymbolInfo,
-    pub align: u64,
+    pub symbol_info: &'b SymbolInfo,
 }
 
 #[derive(Clone, Copy, Debug)]
 pub struct SymbolData {
     pub symbol_id: SymbolId,
-    pub section_id: SectionId,
+    /// `None` for symbols defined outside of this object
+    pub section_id: Option<SectionId>,
     pub offset: u64,
+    pub visibility: Visibility,
+    pub weak: bool,
 }
 
 #[derive(Error, Debug)]
@@ -40,10 +46,11 @@ pub enum ElfError {
 }
 
 impl<'a> Elf<'a> {
-    pub fn new() -> Self {
+    pub fn new(architecture: Architecture) -> Self {
         Self {
-            elf: Object::new(BinaryFormat::Elf, Architecture::Riscv64, Endianness::Little),
+            elf: Object::new(BinaryFormat::Elf, architecture, Endianness::Little),
             values: HashMap::new(),
+            sections: HashMap::new(),
             symbols: HashMap::new(),
         }
     }
@@ -51,27 +58,63 @@ impl<'a> Elf<'a> {
         let id = self.elf.add_section(vec![], name.as_bytes().to_vec(), kind);
 
         self.values.insert(id, 0);
+        self.sections.insert(name.to_string(), id);
 
         id
     }
+    /// Sets `flags` in the `e_flags` field of the ELF header.
+    pub fn add_flags(&mut self, flags: u32) {
+        let e_flags = match self.elf.flags {
+            FileFlags::Elf { e_flags, .. } => e_flags,
+            _ => 0,
+        };
+
+        self.elf.flags = FileFlags::Elf {
+            os_abi: 0,
+            abi_version: 0,
+            e_flags: e_flags | flags,
+        };
+    }
+    /// Adds the `.riscv.attributes` section.
+    pub fn add_attributes(&mut self, attributes: &Attributes) {
+        let id = self.elf.add_section(
+            vec![],
+            b".riscv.attributes".to_vec(),
+            SectionKind::Elf(SHT_RISCV_ATTRIBUTES),
+        );
+
+        self.elf.section_mut(id).set_data(attributes.encode(), 1);
+    }
     pub fn write_section(&mut self, id: SectionId, content: &[u8], align: u64) {
         self.elf.section_mut(id).append_data(content, align);
     }
+    /// Offset where the next `write_section` with the same `align` will place its content.
+    pub fn section_end(&self, id: SectionId, align: u64) -> u64 {
+        (self.elf.section(id).data().len() as u64).next_multiple_of(align)
+    }
+    pub fn align_values(&mut self, id: SectionId, align: u64) {
+        let value = self.values.get_mut(&id).expect("Section not found");
+
+        *value = value.next_multiple_of(align);
+    }
+    pub fn reserve(&mut self, id: SectionId, size: u64) {
+        *self.values.get_mut(&id).expect("Section not found") += size;
+    }
     pub fn get_symbol(&self, name: &String) -> Result<&SymbolData, ElfError> {
        match self.symbols.get(name) {
             Some(v) => Ok(v),
             None => Err(ElfError::SymbolNotExists(name.clone())),
        } 
     }
-    pub fn create_symbol(&mut self, info: CreateSectionInfo) -> SymbolId {
+    pub fn create_symbol(&mut self, info: CreateSymbolInfo<'_>) -> SymbolId {
         // TODO: replace this with an error.
         let value = {
             *self.values.get(&info.section_id).expect("Section not found")
         };
         let id = self.elf.add_symbol(Symbol {
             name: info.name.as_bytes().to_vec(),
-            value: value,
-            size: info.content.len() as u64,
+            value,
+            size: info.size,
             weak: info.symbol_info.weak,
             kind: info.symbol_kind,
             scope: SymbolScope::Linkage,
@@ -85,19 +128,51 @@ impl<'a> Elf<'a> {
             },
         });
 
-        self.write_section(info.section_id, info.content, info.align);
-
         self.symbols.insert(info.name.to_string(), SymbolData {
             symbol_id: id,
-            section_id: info.section_id,
+            section_id: Some(info.section_id),
             offset: value,
+            visibility: info.symbol_info.visibility,
+            weak: info.symbol_info.weak,
         });
 
-        *self.values.get_mut(&info.section_id).expect("Expected symbol") += info.content.len() as u64;
-        
+        self.reserve(info.section_id, info.size);
 
         id
     }
+    /// Looks up `name`, declaring it as an undefined symbol for the linker when it is not defined in this object.
+    pub fn symbol_or_undefined(&mut self, name: &String) -> SymbolData {
+        match self.get_symbol(name) {
+            Ok(data) => *data,
+            Err(_) => self.undefined_symbol(name),
+        }
+    }
+    pub fn undefined_symbol(&mut self, name: &str) -> SymbolData {
+        let id = self.elf.add_symbol(Symbol {
+            name: name.as_bytes().to_vec(),
+            value: 0,
+            size: 0,
+            weak: false,
+            kind: SymbolKind::Unknown,
+            scope: SymbolScope::Linkage,
+            section: SymbolSection::Undefined,
+            flags: SymbolFlags::Elf {
+                st_info: 0x10,
+                st_other: 0x0,
+            },
+        });
+        let data = SymbolData {
+            symbol_id: id,
+            section_id: None,
+            offset: 0,
+            visibility: Visibility::Global,
+            weak: false,
+        };
+
+        self.symbols.insert(name.to_string(), data);
+
+        data
+    }
 
     pub fn create_reallocation(&mut self, id: SectionId, relocation: Relocation) -> Result<(), ElfError> {
         self.elf.add_relocation(id,  relocation)?;
@@ -1,16 +1,13 @@
 // fix this code
 
-use std::{
-    cell::OnceCell,
-    fs::{File, read_to_string},
-    path::Path,
-    sync::{Arc, Once},
-};
+use std::{fs::read_to_string, path::Path};
 
 use logos::Logos;
 
 use crate::parser::{ast::nodes_from_tokens, token::Token};
+use crate::riscv::isa::{Abi, IsaError};
 use clap::Parser;
+use colored::Colorize;
 
 mod elf;
 mod parser;
@@ -23,19 +20,63 @@ pub struct Cli {
 
     #[clap(short, long)]
     pub output: String,
+
+    /// Target ISA string like `rv64imafdc_zicsr_zba` or a profile like
+    /// `rva22u64`, `rv64gc` when it is missing
+    #[clap(long)]
+    pub march: Option<String>,
+
+    /// Calling convention written in the ELF header, `lp64d` or `ilp32`,
+    /// picked from the ISA when it is missing
+    #[clap(long)]
+    pub mabi: Option<String>,
+
+    /// TOML or JSON file describing extra instructions, see `riscv/custom.rs`
+    #[clap(long)]
+    pub isa_ext: Option<String>,
+}
+
+/// Selects the target ISA and checks the ABI against it.
+fn target(cli: &Cli) -> Result<Abi, IsaError> {
+    if let Some(march) = &cli.march {
+        riscv::isa::set_march(march)?;
+    }
+
+    let abi = match &cli.mabi {
+        Some(name) => Abi::parse(name)?,
+        None => Abi::for_target(),
+    };
+
+    abi.check()
 }
 
 fn main() {
     let cli = Cli::parse();
 
+    let abi = target(&cli).unwrap_or_else(|err| {
+        println!("{}", err.to_string().bright_red());
+        std::process::exit(1);
+    });
+
+    if let Some(path) = &cli.isa_ext {
+        riscv::custom::load_isa_ext(path).unwrap_or_else(|err| {
+            println!("{}", err.to_string().bright_red());
+            std::process::exit(1);
+        });
+    }
+
     let code = read_to_string(cli.file).unwrap();
 
+    // `.option` directives change C while parsing, compression starts from the target
+    let rvc = riscv::isa::is_enabled(riscv::isa::Extension::C);
     let mut t = Token::lexer(&code);
 
     let nodes = nodes_from_tokens(&mut t, code.clone());
-    println!("{:#?}", nodes);
-    let elf = riscv::encode_sections(nodes.0, nodes.1);
-    
-    println!("{}", cli.output);
-    elf.write(&Path::new(&cli.output));
+    let sections = riscv::compress::compress_sections(nodes.0, &nodes.1, rvc);
+    let elf = riscv::encode_sections(sections, nodes.1, abi, rvc).unwrap_or_else(|err| {
+        println!("{}", err.to_string().bright_red());
+        std::process::exit(1);
+    });
+
+    elf.write(Path::new(&cli.output));
 }
@@ -4,30 +4,45 @@ use std::{
 };
 
 use super::token::Token;
-use crate::utils::{check_num, token_to_identifier, token_to_name, token_to_reg, token_to_string};
+use crate::elf::attributes::{AttributeValue, TAG_RISCV_ARCH, is_text_tag};
+use crate::riscv::{
+    custom::{CustomInsn, custom_insn},
+    insn::Insn,
+    isa::{Extension, arch_string, disable, enable, is_enabled, set_option_arch, xlen},
+    li_nodes,
+    vector::{Operand, VectorForm, VectorIns},
+};
+use crate::utils::{
+    check_extension, check_num, fence_set, freg_number, reg_number, rounding_mode,
+    token_to_attribute_tag,
+    token_to_csr,
+    token_to_fence_set, token_to_freg, token_to_identifier, token_to_insn_reg, token_to_name,
+    token_to_opcode, token_to_reg,
+    token_to_fli, token_to_string, token_to_vreg, token_to_vtype, vtype_policy,
+};
 use colored::Colorize;
 use logos::Lexer;
-use thiserror::Error;
 
 pub static LINE: AtomicU64 = AtomicU64::new(0);
 
 pub static SUCCESS: AtomicBool = AtomicBool::new(true);
 
-#[derive(Debug, Error)]
-pub enum AstError {}
-
 // thanks to: https://github.com/Brayan-724/amrisk, for the original macro
 
 macro_rules! generate_nodes {
     (
         $(
-            $name:ident => [$($arg:ident),*]
+            $name:ident $(($suffix:ident))? => [$($arg:ident),*]
         ),* $(,)?
     ) => {
         #[derive(Debug, Clone)]
         pub enum AstNode {
             $(
                 $name {
+                    $(
+                        #[doc = stringify!($suffix)]
+                        $suffix: generate_nodes!(@arg_ty $suffix),
+                    )?
                     $(
                         #[doc = stringify!($arg)]
                         $arg: generate_nodes!(@arg_ty $arg),
@@ -37,6 +52,17 @@ macro_rules! generate_nodes {
             Section { name: String, content: Vec<AstNode> },
             Label { name: String, content: Vec<AstNode> },
             Assci { seq: Vec<u8> },
+            Jal { rd: u32, symbol: String },
+            Jalr { rd: u32, imm: u64, rs1: u32 },
+            OptionRvc { enabled: bool },
+            Fence { pred: u32, succ: u32 },
+            Attribute { tag: u64, value: AttributeValue },
+            Insn { insn: Insn },
+            SfenceVma { rs1: u32, rs2: u32 },
+            HfenceVvma { rs1: u32, rs2: u32 },
+            HfenceGvma { rs1: u32, rs2: u32 },
+            /// `vd` is `vs3` in stores, `vs1` holds the register or immediate operand
+            Vector { ins: VectorIns, vd: u32, vs2: u32, vs1: u64, vm: u32 },
         }
 
 
@@ -51,14 +77,20 @@ macro_rules! generate_nodes {
                 LINE.store(line as u64, Ordering::SeqCst);
 
                 match token {
-                    Ok(t) => match t {
+                    Ok(t) => {
+                        check_extension(&t, lex);
+
+                        match t {
                         $(
-                            Token::$name => {
+                            Token::$name $(($suffix))? => {
                                 $(
                                   let $arg = generate_nodes!(@fn_ty $arg, lex);
                                 )*
 
                                 ctx.push(AstNode::$name {
+                                    $(
+                                        $suffix,
+                                    )?
                                     $(
                                         $arg,
                                     )*
@@ -81,6 +113,14 @@ macro_rules! generate_nodes {
                                 rs1: 0,
                                 imm: 0
                             });
+                        }
+                        Token::Li => {
+                            let rd = next_reg(lex);
+                            let imm = next_li_imm(lex);
+
+                            for node in li_nodes(rd, imm) {
+                                ctx.push(node);
+                            }
                         }
                          Token::Label(s) => {
                     ctx.push_label();
@@ -108,7 +148,240 @@ macro_rules! generate_nodes {
                         seq: assci.into_bytes(),
                     });
                 }
+                Token::Jal => {
+                    // jal label, links into ra
+                    let rd = match reg_number(&peek_token(lex)) {
+                        Some(_) => next_reg(lex),
+                        None => 1,
+                    };
+                    let symbol = next_identifier(lex);
+
+                    ctx.push(AstNode::Jal { rd, symbol });
+                }
+                Token::Jalr => {
+                    let reg = next_reg(lex);
+
+                    let node = match peek_token(lex) {
+                        Token::Number(_) | Token::NegNumber(_) => {
+                            let imm = next_num(lex);
+                            let rs1 = next_in_paren(lex, next_reg);
+
+                            AstNode::Jalr { rd: reg, imm, rs1 }
+                        }
+                        // jalr rs1, links into ra
+                        _ => AstNode::Jalr { rd: 1, imm: 0, rs1: reg },
+                    };
+
+                    ctx.push(node);
+                }
+                Token::Vector(ins) => {
+                    let node = next_vector(lex, ins);
+
+                    ctx.push(node);
+                }
+                Token::Fence => {
+                    // fence alone orders everything
+                    let node = match fence_set(&peek_token(lex)) {
+                        Some(_) => AstNode::Fence {
+                            pred: next_fence_set(lex),
+                            succ: next_fence_set(lex),
+                        },
+                        None => AstNode::Fence { pred: 0b1111, succ: 0b1111 },
+                    };
+
+                    ctx.push(node);
+                }
+                Token::Pause => {
+                    ctx.push(AstNode::Fence { pred: 0b0001, succ: 0 });
+                }
+                Token::NtlP1 | Token::NtlPall | Token::NtlS1 | Token::NtlAll => {
+                    ctx.push(AstNode::Add { rd: 0, rs1: 0, rs2: ntl_register(&t) });
+                }
+                Token::CNtlP1 | Token::CNtlPall | Token::CNtlS1 | Token::CNtlAll => {
+                    ctx.push(AstNode::CAdd { rd: 0, rs2: ntl_register(&t) });
+                }
+                Token::SfenceVma | Token::HfenceVvma | Token::HfenceGvma => {
+                    let rs1 = match reg_number(&peek_token(lex)) {
+                        Some(_) => next_reg(lex),
+                        None => 0,
+                    };
+                    let rs2 = match reg_number(&peek_token(lex)) {
+                        Some(_) => next_reg(lex),
+                        None => 0,
+                    };
+
+                    ctx.push(match t {
+                        Token::HfenceVvma => AstNode::HfenceVvma { rs1, rs2 },
+                        Token::HfenceGvma => AstNode::HfenceGvma { rs1, rs2 },
+                        _ => AstNode::SfenceVma { rs1, rs2 },
+                    });
+                }
+                Token::ZextW => {
+                    let rd = next_reg(lex);
+                    let rs1 = next_reg(lex);
+
+                    ctx.push(AstNode::AddUw { rd, rs1, rs2: 0 });
+                }
+                Token::Csrr => {
+                    let rd = next_reg(lex);
+                    let csr = next_csr(lex);
+
+                    ctx.push(AstNode::Csrrs { rd, csr, rs1: 0 });
+                }
+                Token::Csrw | Token::Csrs | Token::Csrc => {
+                    let csr = next_csr(lex);
+                    let rs1 = next_reg(lex);
+
+                    ctx.push(match t {
+                        Token::Csrw => AstNode::Csrrw { rd: 0, csr, rs1 },
+                        Token::Csrs => AstNode::Csrrs { rd: 0, csr, rs1 },
+                        _ => AstNode::Csrrc { rd: 0, csr, rs1 },
+                    });
+                }
+                Token::Csrwi | Token::Csrsi | Token::Csrci => {
+                    let csr = next_csr(lex);
+                    let imm = next_num(lex);
+
+                    ctx.push(match t {
+                        Token::Csrwi => AstNode::Csrrwi { rd: 0, csr, imm },
+                        Token::Csrsi => AstNode::Csrrsi { rd: 0, csr, imm },
+                        _ => AstNode::Csrrci { rd: 0, csr, imm },
+                    });
+                }
+                Token::Rdcycle | Token::Rdtime | Token::Rdinstret => {
+                    let rd = next_reg(lex);
+                    let csr = match t {
+                        Token::Rdcycle => 0xC00,
+                        Token::Rdtime => 0xC01,
+                        _ => 0xC02,
+                    };
+
+                    ctx.push(AstNode::Csrrs { rd, csr, rs1: 0 });
+                }
+                Token::Frcsr => {
+                    let rd = next_reg(lex);
+
+                    ctx.push(AstNode::Csrrs { rd, csr: 0x003, rs1: 0 });
+                }
+                Token::Fscsr => {
+                    let reg = next_reg(lex);
+
+                    // fscsr rs1, swaps without reading the old value
+                    let node = match reg_number(&peek_token(lex)) {
+                        Some(_) => AstNode::Csrrw { rd: reg, csr: 0x003, rs1: next_reg(lex) },
+                        None => AstNode::Csrrw { rd: 0, csr: 0x003, rs1: reg },
+                    };
+
+                    ctx.push(node);
+                }
+                Token::Attribute => {
+                    let tag = next_attribute_tag(lex);
+                    let value = if is_text_tag(tag) {
+                        AttributeValue::Text(next_string(lex))
+                    } else {
+                        AttributeValue::Number(next_num(lex))
+                    };
+
+                    // `.attribute arch` also selects the extensions, like GNU as
+                    let value = match value {
+                        AttributeValue::Text(arch) if tag == TAG_RISCV_ARCH => {
+                            let rvc = is_enabled(Extension::C);
+
+                            if let Err(err) = set_option_arch(&arch) {
+                                SUCCESS.store(false, Ordering::SeqCst);
+                                println!(
+                                    "{}\n\tLine: {}",
+                                    err.to_string().bright_red(),
+                                    LINE.load(Ordering::Relaxed)
+                                );
+                            }
+
+                            if is_enabled(Extension::C) != rvc {
+                                ctx.push(AstNode::OptionRvc { enabled: !rvc });
+                            }
+
+                            AttributeValue::Text(arch_string())
+                        }
+                        value => value,
+                    };
+
+                    ctx.push(AstNode::Attribute { tag, value });
+                }
+                Token::Insn => {
+                    if let Some(insn) = next_insn(lex) {
+                        ctx.push(AstNode::Insn { insn });
+                    }
+                }
+                Token::OptionDirective => {
+                    let option = next_identifier(lex);
+
+                    match option.as_str() {
+                        "rvc" => {
+                            enable(Extension::C);
+                            ctx.push(AstNode::OptionRvc { enabled: true });
+                        }
+                        "norvc" => {
+                            disable(Extension::C);
+                            ctx.push(AstNode::OptionRvc { enabled: false });
+                        }
+                        "arch" => {
+                            let rvc = is_enabled(Extension::C);
+
+                            for change in next_arch_changes(lex) {
+                                if let Err(err) = set_option_arch(&change) {
+                                    SUCCESS.store(false, Ordering::SeqCst);
+                                    println!(
+                                        "{}\n\tLine: {}",
+                                        err.to_string().bright_red(),
+                                        LINE.load(Ordering::Relaxed)
+                                    );
+                                }
+                            }
+
+                            // `+c` and `-c` switch compression like `rvc` and `norvc`
+                            if is_enabled(Extension::C) != rvc {
+                                ctx.push(AstNode::OptionRvc { enabled: !rvc });
+                            }
+                        }
+                        _ => {
+                            SUCCESS.store(false, Ordering::SeqCst);
+                            println!(
+                                "{}:\n \tFound: {:?}\r\n\tLine: {}",
+                                "Error, Unknown option".bright_red(),
+                                option,
+                                LINE.load(Ordering::Relaxed)
+                            );
+                        }
+                    }
+                }
                 Token::Comment => {}
+                Token::Identifier(name) => {
+                    let mnemonic = next_mnemonic(lex, name);
+
+                    match custom_insn(&mnemonic) {
+                        Some(custom) => match next_custom(lex, custom) {
+                            Some(insn) => ctx.push(AstNode::Insn { insn }),
+                            None => {
+                                SUCCESS.store(false, Ordering::SeqCst);
+                                println!(
+                                    "{}:\n \tFound: {:?}\r\n\tLine: {}",
+                                    "Error, Invalid instruction spec".bright_red(),
+                                    mnemonic,
+                                    LINE.load(Ordering::Relaxed)
+                                );
+                            }
+                        },
+                        None => {
+                            SUCCESS.store(false, Ordering::SeqCst);
+                            println!(
+                                "{}:\n \tFound: {:?}\r\n\tLine: {}",
+                                "Error, Unknown instruction".bright_red(),
+                                mnemonic,
+                                LINE.load(Ordering::Relaxed)
+                            );
+                        }
+                    }
+                }
                     _ => {
                          SUCCESS.store(false, Ordering::SeqCst);
                     println!(
@@ -118,6 +391,7 @@ macro_rules! generate_nodes {
                         LINE.load(Ordering::Relaxed)
                     );
                     },
+                    }
                     },
                     Err(_) => {
                         SUCCESS.store(false, Ordering::SeqCst);
@@ -150,6 +424,16 @@ macro_rules! generate_nodes {
     (@fn_ty imm, $lex: expr) => { next_num($lex) };
     (@fn_ty symbol, $lex: expr) => { next_identifier($lex) };
     (@fn_ty paren_rs1, $lex: expr) => { next_in_paren($lex, next_reg) };
+    (@fn_ty frd, $lex: expr) => { next_freg($lex) };
+    (@fn_ty frs1, $lex: expr) => { next_freg($lex) };
+    (@fn_ty frs2, $lex: expr) => { next_freg($lex) };
+    (@fn_ty frs3, $lex: expr) => { next_freg($lex) };
+    (@fn_ty rm, $lex: expr) => { next_rm($lex) };
+    (@fn_ty csr, $lex: expr) => { next_csr($lex) };
+    (@fn_ty vtype, $lex: expr) => { next_vtype($lex) };
+    (@fn_ty bs, $lex: expr) => { next_num($lex) };
+    (@fn_ty fli, $lex: expr) => { next_fli($lex) };
+    (@fn_ty rnum, $lex: expr) => { next_num($lex) };
 
     (@arg_ty rd) => { u32 };
     (@arg_ty paren_rs1) => { u32 };
@@ -157,28 +441,348 @@ macro_rules! generate_nodes {
     (@arg_ty rs2) => { u32 };
     (@arg_ty imm) => { u64 };
     (@arg_ty symbol) => { String };
+    (@arg_ty frd) => { u32 };
+    (@arg_ty frs1) => { u32 };
+    (@arg_ty frs2) => { u32 };
+    (@arg_ty frs3) => { u32 };
+    (@arg_ty rm) => { Option<u32> };
+    (@arg_ty csr) => { u32 };
+    (@arg_ty vtype) => { u32 };
+    (@arg_ty bs) => { u64 };
+    (@arg_ty fli) => { u32 };
+    (@arg_ty rnum) => { u64 };
+    (@arg_ty aqrl) => { u32 };
+    (@arg_ty fmt) => { u32 };
+    (@arg_ty fmts) => { (u32, u32) };
 }
 
 // original macro by https://github.com/Brayan-724/amrisk
 generate_nodes! {
     Addi => [rd, rs1, imm],
+    Slti => [rd, rs1, imm],
+    Sltiu => [rd, rs1, imm],
+    Xori => [rd, rs1, imm],
+    Ori => [rd, rs1, imm],
+    Andi => [rd, rs1, imm],
+    Slli => [rd, rs1, imm],
+    Srli => [rd, rs1, imm],
+    Srai => [rd, rs1, imm],
+    Addiw => [rd, rs1, imm],
+    Slliw => [rd, rs1, imm],
+    Srliw => [rd, rs1, imm],
+    Sraiw => [rd, rs1, imm],
+
     Sub => [rd, rs1, rs2],
     Add => [rd, rs1, rs2],
     Xor => [rd, rs1, rs2],
+    Or => [rd, rs1, rs2],
+    And => [rd, rs1, rs2],
     Sll => [rd, rs1, rs2],
     Srl => [rd, rs1, rs2],
     Sra => [rd, rs1, rs2],
     Slt => [rd, rs1, rs2],
     Sltu => [rd, rs1, rs2],
+    Addw => [rd, rs1, rs2],
+    Subw => [rd, rs1, rs2],
+    Sllw => [rd, rs1, rs2],
+    Srlw => [rd, rs1, rs2],
+    Sraw => [rd, rs1, rs2],
+
+    Mul => [rd, rs1, rs2],
+    Mulh => [rd, rs1, rs2],
+    Mulhsu => [rd, rs1, rs2],
+    Mulhu => [rd, rs1, rs2],
+    Div => [rd, rs1, rs2],
+    Divu => [rd, rs1, rs2],
+    Rem => [rd, rs1, rs2],
+    Remu => [rd, rs1, rs2],
+    Mulw => [rd, rs1, rs2],
+    Divw => [rd, rs1, rs2],
+    Divuw => [rd, rs1, rs2],
+    Remw => [rd, rs1, rs2],
+    Remuw => [rd, rs1, rs2],
+
+    Sh1add => [rd, rs1, rs2],
+    Sh2add => [rd, rs1, rs2],
+    Sh3add => [rd, rs1, rs2],
+    AddUw => [rd, rs1, rs2],
+    Sh1addUw => [rd, rs1, rs2],
+    Sh2addUw => [rd, rs1, rs2],
+    Sh3addUw => [rd, rs1, rs2],
+    Andn => [rd, rs1, rs2],
+    Orn => [rd, rs1, rs2],
+    Xnor => [rd, rs1, rs2],
+    Min => [rd, rs1, rs2],
+    Minu => [rd, rs1, rs2],
+    Max => [rd, rs1, rs2],
+    Maxu => [rd, rs1, rs2],
+    Rol => [rd, rs1, rs2],
+    Ror => [rd, rs1, rs2],
+    Rolw => [rd, rs1, rs2],
+    Rorw => [rd, rs1, rs2],
+    Clmul => [rd, rs1, rs2],
+    Clmulr => [rd, rs1, rs2],
+    Clmulh => [rd, rs1, rs2],
+    Bclr => [rd, rs1, rs2],
+    Bext => [rd, rs1, rs2],
+    Binv => [rd, rs1, rs2],
+    Bset => [rd, rs1, rs2],
+    SlliUw => [rd, rs1, imm],
+    Rori => [rd, rs1, imm],
+    Roriw => [rd, rs1, imm],
+    Bclri => [rd, rs1, imm],
+    Bexti => [rd, rs1, imm],
+    Binvi => [rd, rs1, imm],
+    Bseti => [rd, rs1, imm],
+    Clz => [rd, rs1],
+    Ctz => [rd, rs1],
+    Cpop => [rd, rs1],
+    Clzw => [rd, rs1],
+    Ctzw => [rd, rs1],
+    Cpopw => [rd, rs1],
+    SextB => [rd, rs1],
+    SextH => [rd, rs1],
+    OrcB => [rd, rs1],
+    Rev8 => [rd, rs1],
+    ZextH => [rd, rs1],
+
+    LrW(aqrl) => [rd, paren_rs1],
+    ScW(aqrl) => [rd, rs2, paren_rs1],
+    AmoSwapW(aqrl) => [rd, rs2, paren_rs1],
+    AmoAddW(aqrl) => [rd, rs2, paren_rs1],
+    AmoXorW(aqrl) => [rd, rs2, paren_rs1],
+    AmoAndW(aqrl) => [rd, rs2, paren_rs1],
+    AmoOrW(aqrl) => [rd, rs2, paren_rs1],
+    AmoMinW(aqrl) => [rd, rs2, paren_rs1],
+    AmoMaxW(aqrl) => [rd, rs2, paren_rs1],
+    AmoMinuW(aqrl) => [rd, rs2, paren_rs1],
+    AmoMaxuW(aqrl) => [rd, rs2, paren_rs1],
+    LrD(aqrl) => [rd, paren_rs1],
+    ScD(aqrl) => [rd, rs2, paren_rs1],
+    AmoSwapD(aqrl) => [rd, rs2, paren_rs1],
+    AmoAddD(aqrl) => [rd, rs2, paren_rs1],
+    AmoXorD(aqrl) => [rd, rs2, paren_rs1],
+    AmoAndD(aqrl) => [rd, rs2, paren_rs1],
+    AmoOrD(aqrl) => [rd, rs2, paren_rs1],
+    AmoMinD(aqrl) => [rd, rs2, paren_rs1],
+    AmoMaxD(aqrl) => [rd, rs2, paren_rs1],
+    AmoMinuD(aqrl) => [rd, rs2, paren_rs1],
+    AmoMaxuD(aqrl) => [rd, rs2, paren_rs1],
+    AmoCasW(aqrl) => [rd, rs2, paren_rs1],
+    AmoCasD(aqrl) => [rd, rs2, paren_rs1],
+    AmoCasQ(aqrl) => [rd, rs2, paren_rs1],
+
+    HlvB => [rd, paren_rs1],
+    HlvBu => [rd, paren_rs1],
+    HlvH => [rd, paren_rs1],
+    HlvHu => [rd, paren_rs1],
+    HlvxHu => [rd, paren_rs1],
+    HlvW => [rd, paren_rs1],
+    HlvWu => [rd, paren_rs1],
+    HlvxWu => [rd, paren_rs1],
+    HlvD => [rd, paren_rs1],
+    HsvB => [rs2, paren_rs1],
+    HsvH => [rs2, paren_rs1],
+    HsvW => [rs2, paren_rs1],
+    HsvD => [rs2, paren_rs1],
+
+    Flw => [frd, imm, paren_rs1],
+    Fld => [frd, imm, paren_rs1],
+    Fsw => [frs2, imm, paren_rs1],
+    Fsd => [frs2, imm, paren_rs1],
+    Flh => [frd, imm, paren_rs1],
+    Fsh => [frs2, imm, paren_rs1],
+    Flq => [frd, imm, paren_rs1],
+    Fsq => [frs2, imm, paren_rs1],
+
+    Fmadd(fmt) => [frd, frs1, frs2, frs3, rm],
+    Fmsub(fmt) => [frd, frs1, frs2, frs3, rm],
+    Fnmsub(fmt) => [frd, frs1, frs2, frs3, rm],
+    Fnmadd(fmt) => [frd, frs1, frs2, frs3, rm],
+
+    Fadd(fmt) => [frd, frs1, frs2, rm],
+    Fsub(fmt) => [frd, frs1, frs2, rm],
+    Fmul(fmt) => [frd, frs1, frs2, rm],
+    Fdiv(fmt) => [frd, frs1, frs2, rm],
+    Fsqrt(fmt) => [frd, frs1, rm],
+    Fsgnj(fmt) => [frd, frs1, frs2],
+    Fsgnjn(fmt) => [frd, frs1, frs2],
+    Fsgnjx(fmt) => [frd, frs1, frs2],
+    Fmin(fmt) => [frd, frs1, frs2],
+    Fmax(fmt) => [frd, frs1, frs2],
+    Feq(fmt) => [rd, frs1, frs2],
+    Flt(fmt) => [rd, frs1, frs2],
+    Fle(fmt) => [rd, frs1, frs2],
+    Fclass(fmt) => [rd, frs1],
+
+    FcvtW(fmt) => [rd, frs1, rm],
+    FcvtWu(fmt) => [rd, frs1, rm],
+    FcvtL(fmt) => [rd, frs1, rm],
+    FcvtLu(fmt) => [rd, frs1, rm],
+    FcvtFW(fmt) => [frd, rs1, rm],
+    FcvtFWu(fmt) => [frd, rs1, rm],
+    FcvtFL(fmt) => [frd, rs1, rm],
+    FcvtFLu(fmt) => [frd, rs1, rm],
+    FcvtFF(fmts) => [frd, frs1, rm],
+
+    FmvXW => [rd, frs1],
+    FmvWX => [frd, rs1],
+    FmvXD => [rd, frs1],
+    FmvDX => [frd, rs1],
+    FmvXH => [rd, frs1],
+    FmvHX => [frd, rs1],
 
+    Fli(fmt) => [frd, fli],
+    Fminm(fmt) => [frd, frs1, frs2],
+    Fmaxm(fmt) => [frd, frs1, frs2],
+    Fround(fmt) => [frd, frs1, rm],
+    Froundnx(fmt) => [frd, frs1, rm],
+    Fleq(fmt) => [rd, frs1, frs2],
+    Fltq(fmt) => [rd, frs1, frs2],
+    FcvtmodWD => [rd, frs1, rm],
+    FmvhXD => [rd, frs1],
+    FmvpDX => [frd, rs1, rs2],
+
+    Fmv(fmt) => [frd, frs1],
+    Fneg(fmt) => [frd, frs1],
+    Fabs(fmt) => [frd, frs1],
+
+    CAddi4spn => [rd, rs1, imm],
+    CFld => [frd, imm, paren_rs1],
+    CLw => [rd, imm, paren_rs1],
+    CFlw => [frd, imm, paren_rs1],
+    CLd => [rd, imm, paren_rs1],
+    CFsd => [frs2, imm, paren_rs1],
+    CSw => [rs2, imm, paren_rs1],
+    CFsw => [frs2, imm, paren_rs1],
+    CSd => [rs2, imm, paren_rs1],
+    CNop => [],
+    CAddi => [rd, imm],
+    CAddiw => [rd, imm],
+    CLi => [rd, imm],
+    CAddi16sp => [rd, imm],
+    CLui => [rd, imm],
+    CSrli => [rd, imm],
+    CSrai => [rd, imm],
+    CAndi => [rd, imm],
+    CSub => [rd, rs2],
+    CXor => [rd, rs2],
+    COr => [rd, rs2],
+    CAnd => [rd, rs2],
+    CSubw => [rd, rs2],
+    CAddw => [rd, rs2],
+    CJ => [symbol],
+    CJal => [symbol],
+    CBeqz => [rs1, symbol],
+    CBnez => [rs1, symbol],
+    CSlli => [rd, imm],
+    CFldsp => [frd, imm, paren_rs1],
+    CLwsp => [rd, imm, paren_rs1],
+    CFlwsp => [frd, imm, paren_rs1],
+    CLdsp => [rd, imm, paren_rs1],
+    CJr => [rs1],
+    CJalr => [rs1],
+    CMv => [rd, rs2],
+    CAdd => [rd, rs2],
+    CEbreak => [],
+    CFsdsp => [frs2, imm, paren_rs1],
+    CSwsp => [rs2, imm, paren_rs1],
+    CFswsp => [frs2, imm, paren_rs1],
+    CSdsp => [rs2, imm, paren_rs1],
+
+    CzeroEqz => [rd, rs1, rs2],
+    CzeroNez => [rd, rs1, rs2],
+
+    CboClean => [paren_rs1],
+    CboFlush => [paren_rs1],
+    CboInval => [paren_rs1],
+    CboZero => [paren_rs1],
+    PrefetchI => [imm, paren_rs1],
+    PrefetchR => [imm, paren_rs1],
+    PrefetchW => [imm, paren_rs1],
+
+    Pack => [rd, rs1, rs2],
+    Packh => [rd, rs1, rs2],
+    Packw => [rd, rs1, rs2],
+    Xperm4 => [rd, rs1, rs2],
+    Xperm8 => [rd, rs1, rs2],
+    Aes64es => [rd, rs1, rs2],
+    Aes64esm => [rd, rs1, rs2],
+    Aes64ds => [rd, rs1, rs2],
+    Aes64dsm => [rd, rs1, rs2],
+    Aes64ks2 => [rd, rs1, rs2],
+    Sha512sum0r => [rd, rs1, rs2],
+    Sha512sum1r => [rd, rs1, rs2],
+    Sha512sig0l => [rd, rs1, rs2],
+    Sha512sig1l => [rd, rs1, rs2],
+    Sha512sig0h => [rd, rs1, rs2],
+    Sha512sig1h => [rd, rs1, rs2],
+    Brev8 => [rd, rs1],
+    Zip => [rd, rs1],
+    Unzip => [rd, rs1],
+    Aes64im => [rd, rs1],
+    Sha256sum0 => [rd, rs1],
+    Sha256sum1 => [rd, rs1],
+    Sha256sig0 => [rd, rs1],
+    Sha256sig1 => [rd, rs1],
+    Sha512sum0 => [rd, rs1],
+    Sha512sum1 => [rd, rs1],
+    Sha512sig0 => [rd, rs1],
+    Sha512sig1 => [rd, rs1],
+    Sm3p0 => [rd, rs1],
+    Sm3p1 => [rd, rs1],
+    Aes64ks1i => [rd, rs1, rnum],
+    Aes32esi => [rd, rs1, rs2, bs],
+    Aes32esmi => [rd, rs1, rs2, bs],
+    Aes32dsi => [rd, rs1, rs2, bs],
+    Aes32dsmi => [rd, rs1, rs2, bs],
+    Sm4ed => [rd, rs1, rs2, bs],
+    Sm4ks => [rd, rs1, rs2, bs],
+
+    Vsetvli => [rd, rs1, vtype],
+    Vsetivli => [rd, imm, vtype],
+    Vsetvl => [rd, rs1, rs2],
+
+    Mv => [rd, rs1],
     La => [rd, symbol],
 
     Lui => [rd, imm],
     Auipc => [rd, imm],
 
+    Lb => [rd, imm, paren_rs1],
+    Lh => [rd, imm, paren_rs1],
+    Lw => [rd, imm, paren_rs1],
+    Ld => [rd, imm, paren_rs1],
+    Lbu => [rd, imm, paren_rs1],
+    Lhu => [rd, imm, paren_rs1],
+    Lwu => [rd, imm, paren_rs1],
+
     Sb => [rs2, imm, paren_rs1],
+    Sh => [rs2, imm, paren_rs1],
+    Sw => [rs2, imm, paren_rs1],
+    Sd => [rs2, imm, paren_rs1],
+
+    Beq => [rs1, rs2, symbol],
+    Bne => [rs1, rs2, symbol],
+    Blt => [rs1, rs2, symbol],
+    Bge => [rs1, rs2, symbol],
+    Bltu => [rs1, rs2, symbol],
+    Bgeu => [rs1, rs2, symbol],
 
     Ecall => [],
+    Ebreak => [],
+    Mret => [],
+    Sret => [],
+    Wfi => [],
+    FenceTso => [],
+    FenceI => [],
+
+    Csrrw => [rd, csr, rs1],
+    Csrrs => [rd, csr, rs1],
+    Csrrc => [rd, csr, rs1],
+    Csrrwi => [rd, csr, imm],
+    Csrrsi => [rd, csr, imm],
+    Csrrci => [rd, csr, imm],
 }
 
 pub fn next_string(lex: &mut Lexer<'_, Token>) -> String {
@@ -187,6 +791,12 @@ pub fn next_string(lex: &mut Lexer<'_, Token>) -> String {
     token_to_string(&s, lex)
 }
 
+pub fn next_attribute_tag(lex: &mut Lexer<'_, Token>) -> u64 {
+    let tag = lex.next().unwrap().unwrap_or_default();
+
+    token_to_attribute_tag(&tag, lex)
+}
+
 pub fn next_in_paren<T, F>(lex: &mut Lexer<'_, Token>, func: F) -> T
 where
     F: Fn(&mut Lexer<'_, Token>) -> T,
@@ -198,6 +808,11 @@ where
     ret
 }
 
+/// Returns the next token without consuming it.
+pub fn peek_token(lex: &Lexer<'_, Token>) -> Token {
+    lex.clone().next().and_then(Result::ok).unwrap_or_default()
+}
+
 pub fn expect_token(lex: &mut Lexer<'_, Token>, expected: Token) {
     let l = lex.next().unwrap().unwrap_or_default();
 
@@ -218,6 +833,154 @@ pub fn next_reg(lex: &mut Lexer<'_, Token>) -> u32 {
     token_to_reg(&reg, lex)
 }
 
+pub fn next_freg(lex: &mut Lexer<'_, Token>) -> u32 {
+    let reg = lex.next().unwrap().unwrap_or_default();
+
+    token_to_freg(&reg, lex)
+}
+
+pub fn next_fli(lex: &mut Lexer<'_, Token>) -> u32 {
+    let value = lex.next().unwrap().unwrap_or_default();
+
+    token_to_fli(&value, lex)
+}
+
+/// Source register of the `add x0, x0, rs2` hint behind a `ntl` instruction.
+fn ntl_register(token: &Token) -> u32 {
+    match token {
+        Token::NtlP1 | Token::CNtlP1 => 2,
+        Token::NtlPall | Token::CNtlPall => 3,
+        Token::NtlS1 | Token::CNtlS1 => 4,
+        _ => 5,
+    }
+}
+
+pub fn next_vreg(lex: &mut Lexer<'_, Token>) -> u32 {
+    let reg = lex.next().unwrap().unwrap_or_default();
+
+    token_to_vreg(&reg, lex)
+}
+
+/// `e32, m1, ta, ma` operands of `vsetvli`, the policies default to undisturbed.
+pub fn next_vtype(lex: &mut Lexer<'_, Token>) -> u32 {
+    let sew = lex.next().unwrap().unwrap_or_default();
+    let lmul = lex.next().unwrap().unwrap_or_default();
+    let mut vtype = token_to_vtype(&sew, &lmul, lex);
+
+    while let Some(policy) = vtype_policy(&peek_token(lex)) {
+        lex.next();
+
+        vtype |= policy;
+    }
+
+    vtype
+}
+
+/// Optional `v0.t` operand, as the `vm` bit.
+pub fn next_vm(lex: &mut Lexer<'_, Token>) -> u32 {
+    if peek_token(lex) != Token::V0t {
+        return 1;
+    }
+
+    lex.next();
+
+    0
+}
+
+fn next_operand(lex: &mut Lexer<'_, Token>, operand: Operand) -> u64 {
+    match operand {
+        Operand::V => next_vreg(lex) as u64,
+        Operand::X => next_reg(lex) as u64,
+        Operand::F => next_freg(lex) as u64,
+        Operand::Simm | Operand::Uimm => next_num(lex),
+    }
+}
+
+/// Operands of the vector instruction `ins`, in the order its form writes them.
+pub fn next_vector(lex: &mut Lexer<'_, Token>, ins: VectorIns) -> AstNode {
+    let aux = ins.aux as u64;
+
+    let (vd, vs2, vs1) = match ins.form {
+        VectorForm::Binary(op) | VectorForm::Carry(op) => {
+            let vd = next_vreg(lex);
+            let vs2 = next_vreg(lex);
+
+            (vd, vs2, next_operand(lex, op))
+        }
+        VectorForm::Macc(op) => {
+            let vd = next_vreg(lex);
+            let vs1 = next_operand(lex, op);
+
+            (vd, next_vreg(lex), vs1)
+        }
+        VectorForm::Unary => (next_vreg(lex), next_vreg(lex), aux),
+        VectorForm::Scalar(op) => (next_operand(lex, op) as u32, next_vreg(lex), aux),
+        VectorForm::Move(op) => (next_vreg(lex), ins.aux, next_operand(lex, op)),
+        VectorForm::Dest => (next_vreg(lex), 0, aux),
+        VectorForm::Dup => {
+            let vd = next_vreg(lex);
+            let vs = next_vreg(lex);
+
+            (vd, vs, vs as u64)
+        }
+        VectorForm::Same => {
+            let vd = next_vreg(lex);
+
+            (vd, vd, vd as u64)
+        }
+        VectorForm::Unit => {
+            let vd = next_vreg(lex);
+
+            (vd, ins.aux, next_in_paren(lex, next_reg) as u64)
+        }
+        VectorForm::Strided => {
+            let vd = next_vreg(lex);
+            let rs1 = next_in_paren(lex, next_reg);
+
+            (vd, next_reg(lex), rs1 as u64)
+        }
+        VectorForm::Indexed => {
+            let vd = next_vreg(lex);
+            let rs1 = next_in_paren(lex, next_reg);
+
+            (vd, next_vreg(lex), rs1 as u64)
+        }
+    };
+
+    let vm = match ins.form {
+        // the carry in is always v0
+        VectorForm::Carry(_) => {
+            expect_token(lex, Token::V0);
+            0
+        }
+        _ if ins.masked => next_vm(lex),
+        _ => 1,
+    };
+
+    AstNode::Vector { ins, vd, vs2, vs1, vm }
+}
+
+/// Optional rounding mode operand, `None` when the instruction leaves it out.
+pub fn next_rm(lex: &mut Lexer<'_, Token>) -> Option<u32> {
+    let rm = rounding_mode(&peek_token(lex))?;
+
+    lex.next();
+
+    Some(rm)
+}
+
+pub fn next_csr(lex: &mut Lexer<'_, Token>) -> u32 {
+    let csr = lex.next().unwrap().unwrap_or_default();
+
+    token_to_csr(&csr, lex)
+}
+
+pub fn next_fence_set(lex: &mut Lexer<'_, Token>) -> u32 {
+    let set = lex.next().unwrap().unwrap_or_default();
+
+    token_to_fence_set(&set, lex)
+}
+
 pub fn next_name(lex: &mut Lexer<'_, Token>) -> String {
     let name = lex.next().unwrap().unwrap_or_default();
 
@@ -230,6 +993,292 @@ pub fn next_identifier(lex: &mut Lexer<'_, Token>) -> String {
     token_to_identifier(&ident, lex)
 }
 
+/// Operands of `.option arch`, an ISA string or a list of `+ext` and `-ext`.
+fn next_arch_changes(lex: &mut Lexer<'_, Token>) -> Vec<String> {
+    let mut changes = Vec::new();
+
+    if let Token::Identifier(isa) = peek_token(lex) {
+        lex.next();
+        changes.push(isa);
+    }
+
+    while let Token::ArchChange(change) = peek_token(lex) {
+        lex.next();
+        changes.push(change);
+    }
+
+    if changes.is_empty() {
+        SUCCESS.store(false, Ordering::SeqCst);
+        println!(
+            "{}\n\tLine: {}",
+            "Syntax Error, Expected ISA string or extension changes:".bright_red(),
+            LINE.load(Ordering::Relaxed)
+        );
+    }
+
+    changes
+}
+
+pub fn next_opcode(lex: &mut Lexer<'_, Token>) -> u32 {
+    let opcode = lex.next().unwrap().unwrap_or_default();
+
+    token_to_opcode(&opcode, lex)
+}
+
+pub fn next_insn_reg(lex: &mut Lexer<'_, Token>) -> u32 {
+    let reg = lex.next().unwrap().unwrap_or_default();
+
+    token_to_insn_reg(&reg, lex)
+}
+
+/// Operands of `.insn`, a format followed by its fields in the GNU as order or
+/// a raw instruction word.
+fn next_insn(lex: &mut Lexer<'_, Token>) -> Option<Insn> {
+    let format = match lex.next().unwrap().unwrap_or_default() {
+        // `.insn 0x13` or `.insn 4, 0x13`
+        Token::Number(value) => {
+            let (len, value) = match peek_token(lex) {
+                Token::Number(_) => (value, next_num(lex)),
+                _ => (raw_insn_len(value), value),
+            };
+
+            if !matches!(len, 2 | 4 | 6 | 8) || (len < 8 && value >> (len * 8) != 0) {
+                SUCCESS.store(false, Ordering::SeqCst);
+                println!(
+                    "{}\n\tFound: {:#x}\n\tLine: {}",
+                    format!("Error, instruction doesn't fit in {len} bytes:").bright_red(),
+                    value,
+                    LINE.load(Ordering::Relaxed)
+                );
+
+                return None;
+            }
+
+            return Some(Insn::Raw { value, len });
+        }
+        Token::Identifier(format) => format,
+        // `sb` is the store byte token
+        Token::Sb => "sb".to_string(),
+        _ => String::new(),
+    };
+
+    let insn = match format.as_str() {
+        "r" => {
+            let (opcode, funct3, funct7) = (next_opcode(lex), next_num(lex), next_num(lex));
+            let (rd, rs1, rs2) = (next_insn_reg(lex), next_insn_reg(lex), next_insn_reg(lex));
+
+            // a fourth register makes it R4, `funct7` then holds `funct2`
+            match (freg_number(&peek_token(lex)), reg_number(&peek_token(lex))) {
+                (None, None) => Insn::R {
+                    opcode,
+                    funct3: funct3 as u32,
+                    funct7: funct7 as u32,
+                    rd,
+                    rs1,
+                    rs2,
+                },
+                _ => Insn::R4 {
+                    opcode,
+                    funct3: funct3 as u32,
+                    funct2: funct7 as u32,
+                    rd,
+                    rs1,
+                    rs2,
+                    rs3: next_insn_reg(lex),
+                },
+            }
+        }
+        "r4" => Insn::R4 {
+            opcode: next_opcode(lex),
+            funct3: next_num(lex) as u32,
+            funct2: next_num(lex) as u32,
+            rd: next_insn_reg(lex),
+            rs1: next_insn_reg(lex),
+            rs2: next_insn_reg(lex),
+            rs3: next_insn_reg(lex),
+        },
+        "i" => {
+            let (opcode, funct3, rd) = (next_opcode(lex), next_num(lex) as u32, next_insn_reg(lex));
+
+            // `rd, rs1, imm` or `rd, imm(rs1)`
+            let (rs1, imm) = match (freg_number(&peek_token(lex)), reg_number(&peek_token(lex))) {
+                (None, None) => {
+                    let imm = next_num(lex);
+
+                    (next_in_paren(lex, next_insn_reg), imm)
+                }
+                _ => (next_insn_reg(lex), next_num(lex)),
+            };
+
+            Insn::I { opcode, funct3, rd, rs1, imm }
+        }
+        "s" => {
+            let (opcode, funct3, rs2) = (next_opcode(lex), next_num(lex) as u32, next_insn_reg(lex));
+            let imm = next_num(lex);
+            let rs1 = next_in_paren(lex, next_insn_reg);
+
+            Insn::S { opcode, funct3, rs2, rs1, imm }
+        }
+        "b" | "sb" => Insn::B {
+            opcode: next_opcode(lex),
+            funct3: next_num(lex) as u32,
+            rs1: next_insn_reg(lex),
+            rs2: next_insn_reg(lex),
+            symbol: next_identifier(lex),
+        },
+        "u" => Insn::U {
+            opcode: next_opcode(lex),
+            rd: next_insn_reg(lex),
+            imm: next_num(lex),
+        },
+        "j" | "uj" => Insn::J {
+            opcode: next_opcode(lex),
+            rd: next_insn_reg(lex),
+            symbol: next_identifier(lex),
+        },
+        "cr" => Insn::Cr {
+            opcode: next_opcode(lex),
+            funct4: next_num(lex) as u32,
+            rd: next_insn_reg(lex),
+            rs2: next_insn_reg(lex),
+        },
+        "ci" => Insn::Ci {
+            opcode: next_opcode(lex),
+            funct3: next_num(lex) as u32,
+            rd: next_insn_reg(lex),
+            imm: next_num(lex),
+        },
+        "ciw" => Insn::Ciw {
+            opcode: next_opcode(lex),
+            funct3: next_num(lex) as u32,
+            rd: next_insn_reg(lex),
+            imm: next_num(lex),
+        },
+        "css" => Insn::Css {
+            opcode: next_opcode(lex),
+            funct3: next_num(lex) as u32,
+            rs2: next_insn_reg(lex),
+            imm: next_num(lex),
+        },
+        "cl" => {
+            let (opcode, funct3, rd) = (next_opcode(lex), next_num(lex) as u32, next_insn_reg(lex));
+            let imm = next_num(lex);
+            let rs1 = next_in_paren(lex, next_insn_reg);
+
+            Insn::Cl { opcode, funct3, rd, rs1, imm }
+        }
+        "cs" => {
+            let (opcode, funct3, rs2) = (next_opcode(lex), next_num(lex) as u32, next_insn_reg(lex));
+            let imm = next_num(lex);
+            let rs1 = next_in_paren(lex, next_insn_reg);
+
+            Insn::Cs { opcode, funct3, rs2, rs1, imm }
+        }
+        "ca" => Insn::Ca {
+            opcode: next_opcode(lex),
+            funct6: next_num(lex) as u32,
+            funct2: next_num(lex) as u32,
+            rd: next_insn_reg(lex),
+            rs2: next_insn_reg(lex),
+        },
+        "cb" => Insn::Cb {
+            opcode: next_opcode(lex),
+            funct3: next_num(lex) as u32,
+            rs1: next_insn_reg(lex),
+            symbol: next_identifier(lex),
+        },
+        "cj" => Insn::Cj {
+            opcode: next_opcode(lex),
+            funct3: next_num(lex) as u32,
+            symbol: next_identifier(lex),
+        },
+        _ => {
+            SUCCESS.store(false, Ordering::SeqCst);
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Syntax Error, Unknown instruction format:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            return None;
+        }
+    };
+
+    Some(insn)
+}
+
+/// Mnemonic starting with `name`, joined with the `.name` parts written right
+/// after it, `cx.mac` is lexed as `cx` and `.mac`.
+fn next_mnemonic(lex: &mut Lexer<'_, Token>, mut name: String) -> String {
+    while let Token::Name(part) = peek_token(lex) {
+        let end = lex.span().end;
+        let mut next = lex.clone();
+        next.next();
+
+        if next.span().start != end {
+            break;
+        }
+
+        lex.next();
+        name.push_str(&part);
+    }
+
+    name
+}
+
+/// Operands of an instruction from `--isa-ext`, in the order of its spec.
+fn next_custom(lex: &mut Lexer<'_, Token>, custom: &CustomInsn) -> Option<Insn> {
+    let mut values = HashMap::new();
+    let mut symbol = String::new();
+
+    for operand in &custom.operands {
+        let value = match operand.as_str() {
+            "frd" | "frs1" | "frs2" | "frs3" => next_freg(lex) as u64,
+            "paren_rs1" => next_in_paren(lex, next_reg) as u64,
+            "imm" => next_num(lex),
+            "symbol" => {
+                symbol = next_identifier(lex);
+                continue;
+            }
+            _ => next_reg(lex) as u64,
+        };
+
+        values.insert(operand.as_str(), value);
+    }
+
+    custom.insn(&values, symbol)
+}
+
+/// Length of a raw instruction word from its low bits, like the decoder would.
+fn raw_insn_len(value: u64) -> u64 {
+    match value {
+        v if v & 0b11 != 0b11 => 2,
+        v if v & 0b11100 != 0b11100 => 4,
+        v if v & 0b111111 == 0b011111 => 6,
+        v if v & 0b1111111 == 0b0111111 => 8,
+        _ => 4,
+    }
+}
+
+/// Constant of `li`, RV32 takes signed or unsigned 32 bit values.
+fn next_li_imm(lex: &mut Lexer<'_, Token>) -> i64 {
+    let imm = next_num(lex) as i64;
+
+    if xlen() == 32 && !(i32::MIN as i64..=u32::MAX as i64).contains(&imm) {
+        SUCCESS.store(false, Ordering::SeqCst);
+
+        println!(
+            "{}\n\tFound: {}\n\tLine: {}",
+            "Error, constant doesn't fit in 32 bits:".bright_red(),
+            lex.slice(),
+            LINE.load(Ordering::Relaxed)
+        );
+    }
+
+    if xlen() == 32 { imm as i32 as i64 } else { imm }
+}
+
 pub fn next_num(lex: &mut Lexer<'_, Token>) -> u64 {
     let val = lex.next().unwrap().unwrap_or_default();
 
@@ -274,41 +1323,18 @@ impl ParserCtx {
         }
     }
     pub fn set_visibility(&mut self, name: String, visibility: Visibility) {
-        if !self.functions_info.contains_key(&name) {
-            self.functions_info.insert(
-                name,
-                SymbolInfo {
-                    visibility,
-                    ..Default::default()
-                },
-            );
-
-            return;
-        }
-
-        self.functions_info.get_mut(&name).unwrap().visibility = visibility;
+        self.functions_info.entry(name).or_default().visibility = visibility;
     }
     pub fn set_weakness(&mut self, name: String, weak: bool) {
-        if !self.functions_info.contains_key(&name) {
-            self.functions_info.insert(
-                name,
-                SymbolInfo {
-                    weak,
-                    ..Default::default()
-                },
-            );
-            return;
-        }
-
-        self.functions_info.get_mut(&name).unwrap().weak = weak;
+        self.functions_info.entry(name).or_default().weak = weak;
     }
 
     pub fn push(&mut self, node: AstNode) {
-        if self.current_label.is_some() {
-            self.current_label.as_mut().unwrap().1.push(node);
+        if let Some(label) = &mut self.current_label {
+            label.1.push(node);
             return;
-        } else if self.current_section.is_some() {
-            self.current_section.as_mut().unwrap().1.push(node);
+        } else if let Some(section) = &mut self.current_section {
+            section.1.push(node);
 
             return;
         }
@@ -328,8 +1354,7 @@ impl ParserCtx {
         }
     }
     pub fn get(mut self) -> (Vec<AstNode>, HashMap<String, SymbolInfo>) {
-        if self.current_section.is_some() {
-            let current = self.current_section.as_ref().unwrap();
+        if let Some(current) = &self.current_section {
             self.nodes.push(AstNode::Section {
                 name: current.0.clone(),
                 content: current.1.clone(),
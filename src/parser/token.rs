@@ -1,6 +1,57 @@
-use logos::Logos;
+use logos::{Lexer, Logos};
 
-#[derive(Logos, Debug, PartialEq, PartialOrd, Ord, Eq)]
+use crate::riscv::vector::{VectorIns, vector_ins};
+
+fn fmt_bits(suffix: &str) -> u32 {
+    match suffix {
+        "s" => 0b00,
+        "d" => 0b01,
+        "h" => 0b10,
+        "q" => 0b11,
+        _ => unreachable!("unknown floating point format {suffix}"),
+    }
+}
+
+/// Floating point format of the last suffix, `fadd.d` or `fcvt.w.d`.
+fn fmt(lex: &mut Lexer<'_, Token>) -> u32 {
+    fmt_bits(lex.slice().rsplit('.').next().unwrap())
+}
+
+/// Floating point format of the destination, `fcvt.d.w`.
+fn dest_fmt(lex: &mut Lexer<'_, Token>) -> u32 {
+    fmt_bits(lex.slice().split('.').nth(1).unwrap())
+}
+
+/// Destination and source formats of a float to float conversion, `fcvt.s.d`.
+fn fmts(lex: &mut Lexer<'_, Token>) -> Option<(u32, u32)> {
+    let mut parts = lex.slice().split('.').skip(1);
+    let dest = fmt_bits(parts.next().unwrap());
+    let src = fmt_bits(parts.next().unwrap());
+
+    (dest != src).then_some((dest, src))
+}
+
+/// Memory ordering suffix of an atomic mnemonic, as the aq/rl bits.
+fn aqrl(lex: &mut Lexer<'_, Token>) -> u32 {
+    let slice = lex.slice();
+
+    if slice.ends_with(".aqrl") {
+        0b11
+    } else if slice.ends_with(".aq") {
+        0b10
+    } else if slice.ends_with(".rl") {
+        0b01
+    } else {
+        0b00
+    }
+}
+
+/// Vector instruction of a dotted `v` mnemonic, `vadd.vv` or `vle32.v`.
+fn vector(lex: &mut Lexer<'_, Token>) -> Option<VectorIns> {
+    vector_ins(lex.slice())
+}
+
+#[derive(Logos, Debug, Clone, PartialEq, PartialOrd, Ord, Eq, Default)]
 #[logos(skip r"[ \t\n\f,]+")]
 pub enum Token {
     // Ins
@@ -9,9 +60,158 @@ pub enum Token {
     #[token("addi")]
     Addi,
 
+    #[token("slti")]
+    Slti,
+
+    #[token("sltiu")]
+    Sltiu,
+
+    #[token("xori")]
+    Xori,
+
+    #[token("ori")]
+    Ori,
+
+    #[token("andi")]
+    Andi,
+
+    #[token("slli")]
+    Slli,
+
+    #[token("srli")]
+    Srli,
+
+    #[token("srai")]
+    Srai,
+
+    #[token("addiw")]
+    Addiw,
+
+    #[token("slliw")]
+    Slliw,
+
+    #[token("srliw")]
+    Srliw,
+
+    #[token("sraiw")]
+    Sraiw,
+
     #[token("ecall")]
     Ecall,
 
+    #[token("ebreak")]
+    Ebreak,
+
+    // Privileged
+    #[token("mret")]
+    Mret,
+
+    #[token("sret")]
+    Sret,
+
+    #[token("wfi")]
+    Wfi,
+
+    #[token("sfence.vma")]
+    SfenceVma,
+
+    // Hypervisor
+    #[token("hfence.vvma")]
+    HfenceVvma,
+
+    #[token("hfence.gvma")]
+    HfenceGvma,
+
+    #[token("hlv.b")]
+    HlvB,
+
+    #[token("hlv.bu")]
+    HlvBu,
+
+    #[token("hlv.h")]
+    HlvH,
+
+    #[token("hlv.hu")]
+    HlvHu,
+
+    #[token("hlvx.hu")]
+    HlvxHu,
+
+    #[token("hlv.w")]
+    HlvW,
+
+    #[token("hlv.wu")]
+    HlvWu,
+
+    #[token("hlvx.wu")]
+    HlvxWu,
+
+    #[token("hlv.d")]
+    HlvD,
+
+    #[token("hsv.b")]
+    HsvB,
+
+    #[token("hsv.h")]
+    HsvH,
+
+    #[token("hsv.w")]
+    HsvW,
+
+    #[token("hsv.d")]
+    HsvD,
+
+    // Fences
+    #[token("fence")]
+    Fence,
+
+    #[token("fence.tso")]
+    FenceTso,
+
+    #[token("fence.i")]
+    FenceI,
+
+    // Zicsr
+    #[token("csrrw")]
+    Csrrw,
+
+    #[token("csrrs")]
+    Csrrs,
+
+    #[token("csrrc")]
+    Csrrc,
+
+    #[token("csrrwi")]
+    Csrrwi,
+
+    #[token("csrrsi")]
+    Csrrsi,
+
+    #[token("csrrci")]
+    Csrrci,
+
+    // Loads
+    #[token("lb")]
+    Lb,
+
+    #[token("lh")]
+    Lh,
+
+    #[token("lw")]
+    Lw,
+
+    #[token("ld")]
+    Ld,
+
+    #[token("lbu")]
+    Lbu,
+
+    #[token("lhu")]
+    Lhu,
+
+    #[token("lwu")]
+    Lwu,
+
     // R Type
     //
     #[token("add")]
@@ -47,10 +247,781 @@ pub enum Token {
     #[token("sltu")]
     Sltu,
 
+    #[token("addw")]
+    Addw,
+
+    #[token("subw")]
+    Subw,
+
+    #[token("sllw")]
+    Sllw,
+
+    #[token("srlw")]
+    Srlw,
+
+    #[token("sraw")]
+    Sraw,
+
+    // M extension
+    #[token("mul")]
+    Mul,
+
+    #[token("mulh")]
+    Mulh,
+
+    #[token("mulhsu")]
+    Mulhsu,
+
+    #[token("mulhu")]
+    Mulhu,
+
+    #[token("div")]
+    Div,
+
+    #[token("divu")]
+    Divu,
+
+    #[token("rem")]
+    Rem,
+
+    #[token("remu")]
+    Remu,
+
+    #[token("mulw")]
+    Mulw,
+
+    #[token("divw")]
+    Divw,
+
+    #[token("divuw")]
+    Divuw,
+
+    #[token("remw")]
+    Remw,
+
+    #[token("remuw")]
+    Remuw,
+
+    // Bit manipulation
+    #[token("sh1add")]
+    Sh1add,
+
+    #[token("sh2add")]
+    Sh2add,
+
+    #[token("sh3add")]
+    Sh3add,
+
+    #[token("add.uw")]
+    AddUw,
+
+    #[token("sh1add.uw")]
+    Sh1addUw,
+
+    #[token("sh2add.uw")]
+    Sh2addUw,
+
+    #[token("sh3add.uw")]
+    Sh3addUw,
+
+    #[token("andn")]
+    Andn,
+
+    #[token("orn")]
+    Orn,
+
+    #[token("xnor")]
+    Xnor,
+
+    #[token("min")]
+    Min,
+
+    #[token("minu")]
+    Minu,
+
+    #[token("max")]
+    Max,
+
+    #[token("maxu")]
+    Maxu,
+
+    #[token("rol")]
+    Rol,
+
+    #[token("ror")]
+    Ror,
+
+    #[token("rolw")]
+    Rolw,
+
+    #[token("rorw")]
+    Rorw,
+
+    #[token("clmul")]
+    Clmul,
+
+    #[token("clmulr")]
+    Clmulr,
+
+    #[token("clmulh")]
+    Clmulh,
+
+    #[token("bclr")]
+    Bclr,
+
+    #[token("bext")]
+    Bext,
+
+    #[token("binv")]
+    Binv,
+
+    #[token("bset")]
+    Bset,
+
+    #[token("slli.uw")]
+    SlliUw,
+
+    #[token("rori")]
+    Rori,
+
+    #[token("roriw")]
+    Roriw,
+
+    #[token("bclri")]
+    Bclri,
+
+    #[token("bexti")]
+    Bexti,
+
+    #[token("binvi")]
+    Binvi,
+
+    #[token("bseti")]
+    Bseti,
+
+    #[token("clz")]
+    Clz,
+
+    #[token("ctz")]
+    Ctz,
+
+    #[token("cpop")]
+    Cpop,
+
+    #[token("clzw")]
+    Clzw,
+
+    #[token("ctzw")]
+    Ctzw,
+
+    #[token("cpopw")]
+    Cpopw,
+
+    #[token("sext.b")]
+    SextB,
+
+    #[token("sext.h")]
+    SextH,
+
+    #[token("orc.b")]
+    OrcB,
+
+    #[token("rev8")]
+    Rev8,
+
+    #[token("zext.h")]
+    ZextH,
+
+    #[token("zext.w")]
+    ZextW,
+
+    // A extension
+    #[regex(r"lr\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    LrW(u32),
+
+    #[regex(r"sc\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    ScW(u32),
+
+    #[regex(r"amoswap\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoSwapW(u32),
+
+    #[regex(r"amoadd\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoAddW(u32),
+
+    #[regex(r"amoxor\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoXorW(u32),
+
+    #[regex(r"amoand\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoAndW(u32),
+
+    #[regex(r"amoor\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoOrW(u32),
+
+    #[regex(r"amomin\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoMinW(u32),
+
+    #[regex(r"amomax\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoMaxW(u32),
+
+    #[regex(r"amominu\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoMinuW(u32),
+
+    #[regex(r"amomaxu\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoMaxuW(u32),
+
+    #[regex(r"lr\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    LrD(u32),
+
+    #[regex(r"sc\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    ScD(u32),
+
+    #[regex(r"amoswap\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoSwapD(u32),
+
+    #[regex(r"amoadd\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoAddD(u32),
+
+    #[regex(r"amoxor\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoXorD(u32),
+
+    #[regex(r"amoand\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoAndD(u32),
+
+    #[regex(r"amoor\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoOrD(u32),
+
+    #[regex(r"amomin\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoMinD(u32),
+
+    #[regex(r"amomax\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoMaxD(u32),
+
+    #[regex(r"amominu\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoMinuD(u32),
+
+    #[regex(r"amomaxu\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoMaxuD(u32),
+
+    // Zacas
+    #[regex(r"amocas\.w(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoCasW(u32),
+
+    #[regex(r"amocas\.d(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoCasD(u32),
+
+    #[regex(r"amocas\.q(\.aq|\.rl|\.aqrl)?", aqrl)]
+    AmoCasQ(u32),
+
+    // F and D extensions
+    #[token("flw")]
+    Flw,
+
+    #[token("fld")]
+    Fld,
+
+    #[token("fsw")]
+    Fsw,
+
+    #[token("fsd")]
+    Fsd,
+
+    #[token("fmv.x.w")]
+    FmvXW,
+
+    #[token("fmv.w.x")]
+    FmvWX,
+
+    #[token("fmv.x.d")]
+    FmvXD,
+
+    #[token("fmv.d.x")]
+    FmvDX,
+
+    // Zfh, Zfhmin and Q extensions
+    #[token("flh")]
+    Flh,
+
+    #[token("fsh")]
+    Fsh,
+
+    #[token("flq")]
+    Flq,
+
+    #[token("fsq")]
+    Fsq,
+
+    #[token("fmv.x.h")]
+    FmvXH,
+
+    #[token("fmv.h.x")]
+    FmvHX,
+
+    #[regex(r"fmadd\.[sdhq]", fmt)]
+    Fmadd(u32),
+
+    #[regex(r"fmsub\.[sdhq]", fmt)]
+    Fmsub(u32),
+
+    #[regex(r"fnmsub\.[sdhq]", fmt)]
+    Fnmsub(u32),
+
+    #[regex(r"fnmadd\.[sdhq]", fmt)]
+    Fnmadd(u32),
+
+    #[regex(r"fadd\.[sdhq]", fmt)]
+    Fadd(u32),
+
+    #[regex(r"fsub\.[sdhq]", fmt)]
+    Fsub(u32),
+
+    #[regex(r"fmul\.[sdhq]", fmt)]
+    Fmul(u32),
+
+    #[regex(r"fdiv\.[sdhq]", fmt)]
+    Fdiv(u32),
+
+    #[regex(r"fsqrt\.[sdhq]", fmt)]
+    Fsqrt(u32),
+
+    #[regex(r"fsgnj\.[sdhq]", fmt)]
+    Fsgnj(u32),
+
+    #[regex(r"fsgnjn\.[sdhq]", fmt)]
+    Fsgnjn(u32),
+
+    #[regex(r"fsgnjx\.[sdhq]", fmt)]
+    Fsgnjx(u32),
+
+    #[regex(r"fmin\.[sdhq]", fmt)]
+    Fmin(u32),
+
+    #[regex(r"fmax\.[sdhq]", fmt)]
+    Fmax(u32),
+
+    #[regex(r"feq\.[sdhq]", fmt)]
+    Feq(u32),
+
+    #[regex(r"flt\.[sdhq]", fmt)]
+    Flt(u32),
+
+    #[regex(r"fle\.[sdhq]", fmt)]
+    Fle(u32),
+
+    #[regex(r"fclass\.[sdhq]", fmt)]
+    Fclass(u32),
+
+    #[regex(r"fmv\.[sdhq]", fmt)]
+    Fmv(u32),
+
+    #[regex(r"fneg\.[sdhq]", fmt)]
+    Fneg(u32),
+
+    #[regex(r"fabs\.[sdhq]", fmt)]
+    Fabs(u32),
+
+    #[regex(r"fcvt\.w\.[sdhq]", fmt)]
+    FcvtW(u32),
+
+    #[regex(r"fcvt\.wu\.[sdhq]", fmt)]
+    FcvtWu(u32),
+
+    #[regex(r"fcvt\.l\.[sdhq]", fmt)]
+    FcvtL(u32),
+
+    #[regex(r"fcvt\.lu\.[sdhq]", fmt)]
+    FcvtLu(u32),
+
+    #[regex(r"fcvt\.[sdhq]\.w", dest_fmt)]
+    FcvtFW(u32),
+
+    #[regex(r"fcvt\.[sdhq]\.wu", dest_fmt)]
+    FcvtFWu(u32),
+
+    #[regex(r"fcvt\.[sdhq]\.l", dest_fmt)]
+    FcvtFL(u32),
+
+    #[regex(r"fcvt\.[sdhq]\.lu", dest_fmt)]
+    FcvtFLu(u32),
+
+    #[regex(r"fcvt\.[sdhq]\.[sdhq]", fmts)]
+    FcvtFF((u32, u32)),
+
+    // Zfa
+    #[regex(r"fli\.[sdhq]", fmt)]
+    Fli(u32),
+
+    #[regex(r"fminm\.[sdhq]", fmt)]
+    Fminm(u32),
+
+    #[regex(r"fmaxm\.[sdhq]", fmt)]
+    Fmaxm(u32),
+
+    #[regex(r"fround\.[sdhq]", fmt)]
+    Fround(u32),
+
+    #[regex(r"froundnx\.[sdhq]", fmt)]
+    Froundnx(u32),
+
+    #[regex(r"fleq\.[sdhq]", fmt)]
+    Fleq(u32),
+
+    #[regex(r"fltq\.[sdhq]", fmt)]
+    Fltq(u32),
+
+    #[token("fcvtmod.w.d")]
+    FcvtmodWD,
+
+    #[token("fmvh.x.d")]
+    FmvhXD,
+
+    #[token("fmvp.d.x")]
+    FmvpDX,
+
+    // C extension
+    #[token("c.addi4spn")]
+    CAddi4spn,
+
+    #[token("c.fld")]
+    CFld,
+
+    #[token("c.lw")]
+    CLw,
+
+    #[token("c.flw")]
+    CFlw,
+
+    #[token("c.ld")]
+    CLd,
+
+    #[token("c.fsd")]
+    CFsd,
+
+    #[token("c.sw")]
+    CSw,
+
+    #[token("c.fsw")]
+    CFsw,
+
+    #[token("c.sd")]
+    CSd,
+
+    #[token("c.nop")]
+    CNop,
+
+    #[token("c.addi")]
+    CAddi,
+
+    #[token("c.addiw")]
+    CAddiw,
+
+    #[token("c.li")]
+    CLi,
+
+    #[token("c.addi16sp")]
+    CAddi16sp,
+
+    #[token("c.lui")]
+    CLui,
+
+    #[token("c.srli")]
+    CSrli,
+
+    #[token("c.srai")]
+    CSrai,
+
+    #[token("c.andi")]
+    CAndi,
+
+    #[token("c.sub")]
+    CSub,
+
+    #[token("c.xor")]
+    CXor,
+
+    #[token("c.or")]
+    COr,
+
+    #[token("c.and")]
+    CAnd,
+
+    #[token("c.subw")]
+    CSubw,
+
+    #[token("c.addw")]
+    CAddw,
+
+    #[token("c.j")]
+    CJ,
+
+    #[token("c.jal")]
+    CJal,
+
+    #[token("c.beqz")]
+    CBeqz,
+
+    #[token("c.bnez")]
+    CBnez,
+
+    #[token("c.slli")]
+    CSlli,
+
+    #[token("c.fldsp")]
+    CFldsp,
+
+    #[token("c.lwsp")]
+    CLwsp,
+
+    #[token("c.flwsp")]
+    CFlwsp,
+
+    #[token("c.ldsp")]
+    CLdsp,
+
+    #[token("c.jr")]
+    CJr,
+
+    #[token("c.jalr")]
+    CJalr,
+
+    #[token("c.mv")]
+    CMv,
+
+    #[token("c.add")]
+    CAdd,
+
+    #[token("c.ebreak")]
+    CEbreak,
+
+    #[token("c.fsdsp")]
+    CFsdsp,
+
+    #[token("c.swsp")]
+    CSwsp,
+
+    #[token("c.fswsp")]
+    CFswsp,
+
+    #[token("c.sdsp")]
+    CSdsp,
+
+    // Zicond
+    #[token("czero.eqz")]
+    CzeroEqz,
+
+    #[token("czero.nez")]
+    CzeroNez,
+
+    // Zihintntl
+    #[token("ntl.p1")]
+    NtlP1,
+
+    #[token("ntl.pall")]
+    NtlPall,
+
+    #[token("ntl.s1")]
+    NtlS1,
+
+    #[token("ntl.all")]
+    NtlAll,
+
+    #[token("c.ntl.p1")]
+    CNtlP1,
+
+    #[token("c.ntl.pall")]
+    CNtlPall,
+
+    #[token("c.ntl.s1")]
+    CNtlS1,
+
+    #[token("c.ntl.all")]
+    CNtlAll,
+
+    // Zicbom, Zicboz and Zicbop
+    #[token("cbo.clean")]
+    CboClean,
+
+    #[token("cbo.flush")]
+    CboFlush,
+
+    #[token("cbo.inval")]
+    CboInval,
+
+    #[token("cbo.zero")]
+    CboZero,
+
+    #[token("prefetch.i")]
+    PrefetchI,
+
+    #[token("prefetch.r")]
+    PrefetchR,
+
+    #[token("prefetch.w")]
+    PrefetchW,
+
+    // Scalar cryptography
+    #[token("pack")]
+    Pack,
+
+    #[token("packh")]
+    Packh,
+
+    #[token("packw")]
+    Packw,
+
+    #[token("xperm4")]
+    Xperm4,
+
+    #[token("xperm8")]
+    Xperm8,
+
+    #[token("aes64es")]
+    Aes64es,
+
+    #[token("aes64esm")]
+    Aes64esm,
+
+    #[token("aes64ds")]
+    Aes64ds,
+
+    #[token("aes64dsm")]
+    Aes64dsm,
+
+    #[token("aes64ks2")]
+    Aes64ks2,
+
+    #[token("sha512sum0r")]
+    Sha512sum0r,
+
+    #[token("sha512sum1r")]
+    Sha512sum1r,
+
+    #[token("sha512sig0l")]
+    Sha512sig0l,
+
+    #[token("sha512sig1l")]
+    Sha512sig1l,
+
+    #[token("sha512sig0h")]
+    Sha512sig0h,
+
+    #[token("sha512sig1h")]
+    Sha512sig1h,
+
+    #[token("brev8")]
+    Brev8,
+
+    #[token("zip")]
+    Zip,
+
+    #[token("unzip")]
+    Unzip,
+
+    #[token("aes64im")]
+    Aes64im,
+
+    #[token("sha256sum0")]
+    Sha256sum0,
+
+    #[token("sha256sum1")]
+    Sha256sum1,
+
+    #[token("sha256sig0")]
+    Sha256sig0,
+
+    #[token("sha256sig1")]
+    Sha256sig1,
+
+    #[token("sha512sum0")]
+    Sha512sum0,
+
+    #[token("sha512sum1")]
+    Sha512sum1,
+
+    #[token("sha512sig0")]
+    Sha512sig0,
+
+    #[token("sha512sig1")]
+    Sha512sig1,
+
+    #[token("sm3p0")]
+    Sm3p0,
+
+    #[token("sm3p1")]
+    Sm3p1,
+
+    #[token("aes64ks1i")]
+    Aes64ks1i,
+
+    #[token("aes32esi")]
+    Aes32esi,
+
+    #[token("aes32esmi")]
+    Aes32esmi,
+
+    #[token("aes32dsi")]
+    Aes32dsi,
+
+    #[token("aes32dsmi")]
+    Aes32dsmi,
+
+    #[token("sm4ed")]
+    Sm4ed,
+
+    #[token("sm4ks")]
+    Sm4ks,
+
+    // V extension
+    #[token("vsetvli")]
+    Vsetvli,
+
+    #[token("vsetivli")]
+    Vsetivli,
+
+    #[token("vsetvl")]
+    Vsetvl,
+
+    #[regex(r"v[a-z0-9]+(\.[a-z0-9]+)+", vector)]
+    Vector(VectorIns),
+
     // S type
     #[token("sb")]
     Sb,
 
+    #[token("sh")]
+    Sh,
+
+    #[token("sw")]
+    Sw,
+
+    #[token("sd")]
+    Sd,
+
+    // B type
+    #[token("beq")]
+    Beq,
+
+    #[token("bne")]
+    Bne,
+
+    #[token("blt")]
+    Blt,
+
+    #[token("bge")]
+    Bge,
+
+    #[token("bltu")]
+    Bltu,
+
+    #[token("bgeu")]
+    Bgeu,
+
+    // J type
+    #[token("jal")]
+    Jal,
+
+    #[token("jalr")]
+    Jalr,
+
     // U type
     #[token("auipc")]
     Auipc,
@@ -65,6 +1036,48 @@ pub enum Token {
     #[token("nop")]
     Nop,
 
+    #[token("li")]
+    Li,
+
+    #[token("pause")]
+    Pause,
+
+    #[token("csrr")]
+    Csrr,
+
+    #[token("csrw")]
+    Csrw,
+
+    #[token("csrs")]
+    Csrs,
+
+    #[token("csrc")]
+    Csrc,
+
+    #[token("csrwi")]
+    Csrwi,
+
+    #[token("csrsi")]
+    Csrsi,
+
+    #[token("csrci")]
+    Csrci,
+
+    #[token("rdcycle")]
+    Rdcycle,
+
+    #[token("rdtime")]
+    Rdtime,
+
+    #[token("rdinstret")]
+    Rdinstret,
+
+    #[token("frcsr")]
+    Frcsr,
+
+    #[token("fscsr")]
+    Fscsr,
+
     // Regs
     #[token("x0")]
     X0,
@@ -261,6 +1274,318 @@ pub enum Token {
     #[token("t6")]
     T6,
 
+    // Float Regs
+    #[token("f0")]
+    F0,
+
+    #[token("ft0")]
+    Ft0,
+
+    #[token("f1")]
+    F1,
+
+    #[token("ft1")]
+    Ft1,
+
+    #[token("f2")]
+    F2,
+
+    #[token("ft2")]
+    Ft2,
+
+    #[token("f3")]
+    F3,
+
+    #[token("ft3")]
+    Ft3,
+
+    #[token("f4")]
+    F4,
+
+    #[token("ft4")]
+    Ft4,
+
+    #[token("f5")]
+    F5,
+
+    #[token("ft5")]
+    Ft5,
+
+    #[token("f6")]
+    F6,
+
+    #[token("ft6")]
+    Ft6,
+
+    #[token("f7")]
+    F7,
+
+    #[token("ft7")]
+    Ft7,
+
+    #[token("f8")]
+    F8,
+
+    #[token("fs0")]
+    Fs0,
+
+    #[token("f9")]
+    F9,
+
+    #[token("fs1")]
+    Fs1,
+
+    #[token("f10")]
+    F10,
+
+    #[token("fa0")]
+    Fa0,
+
+    #[token("f11")]
+    F11,
+
+    #[token("fa1")]
+    Fa1,
+
+    #[token("f12")]
+    F12,
+
+    #[token("fa2")]
+    Fa2,
+
+    #[token("f13")]
+    F13,
+
+    #[token("fa3")]
+    Fa3,
+
+    #[token("f14")]
+    F14,
+
+    #[token("fa4")]
+    Fa4,
+
+    #[token("f15")]
+    F15,
+
+    #[token("fa5")]
+    Fa5,
+
+    #[token("f16")]
+    F16,
+
+    #[token("fa6")]
+    Fa6,
+
+    #[token("f17")]
+    F17,
+
+    #[token("fa7")]
+    Fa7,
+
+    #[token("f18")]
+    F18,
+
+    #[token("fs2")]
+    Fs2,
+
+    #[token("f19")]
+    F19,
+
+    #[token("fs3")]
+    Fs3,
+
+    #[token("f20")]
+    F20,
+
+    #[token("fs4")]
+    Fs4,
+
+    #[token("f21")]
+    F21,
+
+    #[token("fs5")]
+    Fs5,
+
+    #[token("f22")]
+    F22,
+
+    #[token("fs6")]
+    Fs6,
+
+    #[token("f23")]
+    F23,
+
+    #[token("fs7")]
+    Fs7,
+
+    #[token("f24")]
+    F24,
+
+    #[token("fs8")]
+    Fs8,
+
+    #[token("f25")]
+    F25,
+
+    #[token("fs9")]
+    Fs9,
+
+    #[token("f26")]
+    F26,
+
+    #[token("fs10")]
+    Fs10,
+
+    #[token("f27")]
+    F27,
+
+    #[token("fs11")]
+    Fs11,
+
+    #[token("f28")]
+    F28,
+
+    #[token("ft8")]
+    Ft8,
+
+    #[token("f29")]
+    F29,
+
+    #[token("ft9")]
+    Ft9,
+
+    #[token("f30")]
+    F30,
+
+    #[token("ft10")]
+    Ft10,
+
+    #[token("f31")]
+    F31,
+
+    #[token("ft11")]
+    Ft11,
+
+    // Rounding modes
+    // Vector regs
+    #[token("v0")]
+    V0,
+
+    #[token("v1")]
+    V1,
+
+    #[token("v2")]
+    V2,
+
+    #[token("v3")]
+    V3,
+
+    #[token("v4")]
+    V4,
+
+    #[token("v5")]
+    V5,
+
+    #[token("v6")]
+    V6,
+
+    #[token("v7")]
+    V7,
+
+    #[token("v8")]
+    V8,
+
+    #[token("v9")]
+    V9,
+
+    #[token("v10")]
+    V10,
+
+    #[token("v11")]
+    V11,
+
+    #[token("v12")]
+    V12,
+
+    #[token("v13")]
+    V13,
+
+    #[token("v14")]
+    V14,
+
+    #[token("v15")]
+    V15,
+
+    #[token("v16")]
+    V16,
+
+    #[token("v17")]
+    V17,
+
+    #[token("v18")]
+    V18,
+
+    #[token("v19")]
+    V19,
+
+    #[token("v20")]
+    V20,
+
+    #[token("v21")]
+    V21,
+
+    #[token("v22")]
+    V22,
+
+    #[token("v23")]
+    V23,
+
+    #[token("v24")]
+    V24,
+
+    #[token("v25")]
+    V25,
+
+    #[token("v26")]
+    V26,
+
+    #[token("v27")]
+    V27,
+
+    #[token("v28")]
+    V28,
+
+    #[token("v29")]
+    V29,
+
+    #[token("v30")]
+    V30,
+
+    #[token("v31")]
+    V31,
+
+    #[token("v0.t", priority = 20)]
+    V0t,
+
+    #[token("rne")]
+    Rne,
+
+    #[token("rtz")]
+    Rtz,
+
+    #[token("rdn")]
+    Rdn,
+
+    #[token("rup")]
+    Rup,
+
+    #[token("rmm")]
+    Rmm,
+
+    #[token("dyn")]
+    Dyn,
+
     // Data Types
     #[token(".ascii")]
     Assci,
@@ -268,6 +1593,9 @@ pub enum Token {
     #[regex(r"\d+",  |lex| {
           lex.slice().trim().parse::<u64>().expect("Invalid number")
     })]
+    #[regex(r"0x[0-9a-fA-F]+", |lex| {
+        u64::from_str_radix(&lex.slice()[2..], 16).expect("Invalid number")
+    })]
     Number(u64),
 
     #[regex(r"-\d+", |lex| {
@@ -275,6 +1603,9 @@ pub enum Token {
     })]
     NegNumber(i64),
 
+    #[regex(r"-?\d+\.\d+([eE][-+]?\d+)?", |lex| lex.slice().to_string())]
+    Float(String),
+
     #[regex(r"'(\\.|[A-Za-z])'", |lex| {
         let a = lex.slice().trim_matches('\'');
         let b = match a {
@@ -305,6 +1636,19 @@ pub enum Token {
     #[token(".globl")]
     Globl,
 
+    #[token(".option")]
+    OptionDirective,
+
+    #[token(".attribute")]
+    Attribute,
+
+    #[token(".insn")]
+    Insn,
+
+    /// Extension added or removed by `.option arch`, `+zba` or `-c`
+    #[regex(r"[+-][a-z][a-z0-9]*", |lex| lex.slice().to_string())]
+    ArchChange(String),
+
     // assembly
     #[regex(r"[A-Za-z_][A-Za-z0-9_]*:", |lex| {
         lex.slice().replace(":", "").to_string()
@@ -329,11 +1673,6 @@ pub enum Token {
     #[regex("#(.*)")]
     Comment,
 
+    #[default]
     Empty,
 }
-
-impl Default for Token {
-    fn default() -> Self {
-        Self::Empty
-    }
-}
@@ -0,0 +1,405 @@
+// Rewrites base instructions into their 16 bit form while `.option rvc` is
+// active, or C is part of the `--march` ISA, before the nodes reach `encode`.
+//
+// Branches and jumps to a local label of the same section start compressed and
+// go back to their 32 bit form when the label is out of reach, until the layout
+// of the section settles. Growing one can only push other targets further away,
+// so it always does.
+
+use std::collections::HashMap;
+
+use crate::parser::ast::{AstNode, SymbolInfo, Visibility};
+
+use super::{fits, isa::xlen, lui_imm, node_size};
+
+pub fn compress_sections(
+    sections: Vec<AstNode>,
+    visibility_map: &HashMap<String, SymbolInfo>,
+    mut rvc: bool,
+) -> Vec<AstNode> {
+    sections
+        .into_iter()
+        .map(|node| match node {
+            AstNode::Section { name, content } => AstNode::Section {
+                name,
+                content: compress_section(content, visibility_map, &mut rvc),
+            },
+            AstNode::OptionRvc { enabled } => {
+                rvc = enabled;
+
+                node
+            }
+            node => node,
+        })
+        .collect()
+}
+
+fn compress_section(
+    mut content: Vec<AstNode>,
+    visibility_map: &HashMap<String, SymbolInfo>,
+    rvc: &mut bool,
+) -> Vec<AstNode> {
+    // the 32 bit form of every branch compressed below, in layout order
+    let mut long_forms = Vec::new();
+
+    for node in instructions_mut(&mut content) {
+        let mut long_form = None;
+
+        match node {
+            AstNode::OptionRvc { enabled } => *rvc = *enabled,
+            _ if *rvc => {
+                if let Some(compressed) = compress(node) {
+                    *node = compressed;
+                } else if let Some(compressed) = compress_branch(node, visibility_map) {
+                    long_form = Some(std::mem::replace(node, compressed));
+                }
+            }
+            _ => {}
+        }
+
+        long_forms.push(long_form);
+    }
+
+    let mut changed = true;
+
+    while changed {
+        let labels = label_offsets(&content);
+        let mut pc = 0;
+
+        changed = false;
+
+        for (node, long_form) in instructions_mut(&mut content).zip(&mut long_forms) {
+            let size = node_size(node);
+
+            if let Some(long) = long_form.take_if(|_| !in_range(node, pc, &labels)) {
+                *node = long;
+                changed = true;
+            }
+
+            pc += size;
+        }
+    }
+
+    content
+}
+
+/// Instructions of a section in layout order, looking into labels.
+fn instructions_mut(content: &mut [AstNode]) -> impl Iterator<Item = &mut AstNode> {
+    content.iter_mut().flat_map(|node| match node {
+        AstNode::Label { content, .. } => content.iter_mut(),
+        node => std::slice::from_mut(node).iter_mut(),
+    })
+}
+
+/// Offset of every label from the start of the section.
+fn label_offsets(content: &[AstNode]) -> HashMap<String, u64> {
+    let mut labels = HashMap::new();
+    let mut offset = 0;
+
+    for node in content {
+        if let AstNode::Label { name, .. } = node {
+            labels.insert(name.clone(), offset);
+        }
+
+        offset += node_size(node);
+    }
+
+    labels
+}
+
+/// Whether the target of a compressed branch at `pc` is within its reach.
+fn in_range(node: &AstNode, pc: u64, labels: &HashMap<String, u64>) -> bool {
+    let (symbol, bits) = match node {
+        AstNode::CBeqz { symbol, .. } | AstNode::CBnez { symbol, .. } => (symbol, 9),
+        AstNode::CJ { symbol } | AstNode::CJal { symbol } => (symbol, 12),
+        _ => return true,
+    };
+
+    labels
+        .get(symbol)
+        .is_some_and(|target| fits(target.wrapping_sub(pc), bits, 2, true))
+}
+
+/// Compressed form of a branch or jump, as long as its target ends up in range.
+/// Labels other files can see are left to the linker.
+fn compress_branch(node: &AstNode, visibility_map: &HashMap<String, SymbolInfo>) -> Option<AstNode> {
+    let local = |symbol: &String| {
+        visibility_map
+            .get(symbol)
+            .is_none_or(|info| matches!(info.visibility, Visibility::Local) && !info.weak)
+    };
+
+    let compressed = match *node {
+        AstNode::Beq {
+            rs1,
+            rs2: 0,
+            ref symbol,
+        } if is_creg(rs1) && local(symbol) => AstNode::CBeqz {
+            rs1,
+            symbol: symbol.clone(),
+        },
+        AstNode::Bne {
+            rs1,
+            rs2: 0,
+            ref symbol,
+        } if is_creg(rs1) && local(symbol) => AstNode::CBnez {
+            rs1,
+            symbol: symbol.clone(),
+        },
+        AstNode::Jal { rd: 0, ref symbol } if local(symbol) => AstNode::CJ {
+            symbol: symbol.clone(),
+        },
+        AstNode::Jal { rd: 1, ref symbol } if xlen() == 32 && local(symbol) => AstNode::CJal {
+            symbol: symbol.clone(),
+        },
+        _ => return None,
+    };
+
+    Some(compressed)
+}
+
+fn is_creg(reg: u32) -> bool {
+    (8..16).contains(&reg)
+}
+
+/// Compressed equivalent of `node`, if it has one.
+fn compress(node: &AstNode) -> Option<AstNode> {
+    let compressed = match *node {
+        AstNode::Addi { rd: 0, rs1: 0, imm: 0 } => AstNode::CNop {},
+        AstNode::Addi { rd, rs1: 2, imm } if rd == 2 && imm != 0 && fits(imm, 10, 16, true) => {
+            AstNode::CAddi16sp { rd, imm }
+        }
+        AstNode::Addi { rd, rs1: 2, imm } if is_creg(rd) && imm != 0 && fits(imm, 10, 4, false) => {
+            AstNode::CAddi4spn { rd, rs1: 2, imm }
+        }
+        AstNode::Addi { rd, rs1, imm } if rd != 0 && rd == rs1 && imm != 0 && fits(imm, 6, 1, true) => {
+            AstNode::CAddi { rd, imm }
+        }
+        AstNode::Addi { rd, rs1: 0, imm } if rd != 0 && fits(imm, 6, 1, true) => {
+            AstNode::CLi { rd, imm }
+        }
+        AstNode::Addi { rd, rs1, imm: 0 } | AstNode::Mv { rd, rs1 } if rd != 0 && rs1 != 0 => {
+            AstNode::CMv { rd, rs2: rs1 }
+        }
+        AstNode::Addiw { rd, rs1, imm } if rd != 0 && rd == rs1 && fits(imm, 6, 1, true) => {
+            AstNode::CAddiw { rd, imm }
+        }
+        AstNode::Lui { rd, imm }
+            if rd != 0
+                && rd != 2
+                && fits(imm, 20, 1, false)
+                && lui_imm(imm) != 0
+                && fits(lui_imm(imm), 6, 1, true) =>
+        {
+            AstNode::CLui { rd, imm }
+        }
+        AstNode::Slli { rd, rs1, imm } if rd != 0 && rd == rs1 && imm != 0 => {
+            AstNode::CSlli { rd, imm }
+        }
+        AstNode::Srli { rd, rs1, imm } if is_creg(rd) && rd == rs1 && imm != 0 => {
+            AstNode::CSrli { rd, imm }
+        }
+        AstNode::Srai { rd, rs1, imm } if is_creg(rd) && rd == rs1 && imm != 0 => {
+            AstNode::CSrai { rd, imm }
+        }
+        AstNode::Andi { rd, rs1, imm } if is_creg(rd) && rd == rs1 && fits(imm, 6, 1, true) => {
+            AstNode::CAndi { rd, imm }
+        }
+
+        // ntl hints
+        AstNode::Add { rd: 0, rs1: 0, rs2 } if (2..=5).contains(&rs2) => {
+            AstNode::CAdd { rd: 0, rs2 }
+        }
+        AstNode::Add { rd, rs1, rs2 } if rd != 0 && rd == rs1 && rs2 != 0 => {
+            AstNode::CAdd { rd, rs2 }
+        }
+        AstNode::Add { rd, rs1, rs2 } if rd != 0 && rd == rs2 && rs1 != 0 => {
+            AstNode::CAdd { rd, rs2: rs1 }
+        }
+        AstNode::Add { rd, rs1: 0, rs2 } if rd != 0 && rs2 != 0 => AstNode::CMv { rd, rs2 },
+        AstNode::Sub { rd, rs1, rs2 } if is_creg(rd) && rd == rs1 && is_creg(rs2) => {
+            AstNode::CSub { rd, rs2 }
+        }
+        AstNode::Subw { rd, rs1, rs2 } if is_creg(rd) && rd == rs1 && is_creg(rs2) => {
+            AstNode::CSubw { rd, rs2 }
+        }
+        AstNode::Xor { rd, rs1, rs2 } if is_creg(rd) && is_creg(rs1) && is_creg(rs2) => {
+            match (rd == rs1, rd == rs2) {
+                (true, _) => AstNode::CXor { rd, rs2 },
+                (_, true) => AstNode::CXor { rd, rs2: rs1 },
+                _ => return None,
+            }
+        }
+        AstNode::Or { rd, rs1, rs2 } if is_creg(rd) && is_creg(rs1) && is_creg(rs2) => {
+            match (rd == rs1, rd == rs2) {
+                (true, _) => AstNode::COr { rd, rs2 },
+                (_, true) => AstNode::COr { rd, rs2: rs1 },
+                _ => return None,
+            }
+        }
+        AstNode::And { rd, rs1, rs2 } if is_creg(rd) && is_creg(rs1) && is_creg(rs2) => {
+            match (rd == rs1, rd == rs2) {
+                (true, _) => AstNode::CAnd { rd, rs2 },
+                (_, true) => AstNode::CAnd { rd, rs2: rs1 },
+                _ => return None,
+            }
+        }
+        AstNode::Addw { rd, rs1, rs2 } if is_creg(rd) && is_creg(rs1) && is_creg(rs2) => {
+            match (rd == rs1, rd == rs2) {
+                (true, _) => AstNode::CAddw { rd, rs2 },
+                (_, true) => AstNode::CAddw { rd, rs2: rs1 },
+                _ => return None,
+            }
+        }
+
+        AstNode::Lw {
+            rd,
+            imm,
+            paren_rs1: 2,
+        } if rd != 0 && fits(imm, 8, 4, false) => AstNode::CLwsp {
+            rd,
+            imm,
+            paren_rs1: 2,
+        },
+        AstNode::Lw { rd, imm, paren_rs1 }
+            if is_creg(rd) && is_creg(paren_rs1) && fits(imm, 7, 4, false) =>
+        {
+            AstNode::CLw { rd, imm, paren_rs1 }
+        }
+        AstNode::Ld {
+            rd,
+            imm,
+            paren_rs1: 2,
+        } if rd != 0 && fits(imm, 9, 8, false) => AstNode::CLdsp {
+            rd,
+            imm,
+            paren_rs1: 2,
+        },
+        AstNode::Ld { rd, imm, paren_rs1 }
+            if is_creg(rd) && is_creg(paren_rs1) && fits(imm, 8, 8, false) =>
+        {
+            AstNode::CLd { rd, imm, paren_rs1 }
+        }
+        AstNode::Fld {
+            frd,
+            imm,
+            paren_rs1: 2,
+        } if fits(imm, 9, 8, false) => AstNode::CFldsp {
+            frd,
+            imm,
+            paren_rs1: 2,
+        },
+        AstNode::Fld {
+            frd,
+            imm,
+            paren_rs1,
+        } if is_creg(frd) && is_creg(paren_rs1) && fits(imm, 8, 8, false) => AstNode::CFld {
+            frd,
+            imm,
+            paren_rs1,
+        },
+        AstNode::Flw {
+            frd,
+            imm,
+            paren_rs1: 2,
+        } if xlen() == 32 && fits(imm, 8, 4, false) => AstNode::CFlwsp {
+            frd,
+            imm,
+            paren_rs1: 2,
+        },
+        AstNode::Flw {
+            frd,
+            imm,
+            paren_rs1,
+        } if xlen() == 32 && is_creg(frd) && is_creg(paren_rs1) && fits(imm, 7, 4, false) => {
+            AstNode::CFlw {
+                frd,
+                imm,
+                paren_rs1,
+            }
+        }
+        AstNode::Sw {
+            rs2,
+            imm,
+            paren_rs1: 2,
+        } if fits(imm, 8, 4, false) => AstNode::CSwsp {
+            rs2,
+            imm,
+            paren_rs1: 2,
+        },
+        AstNode::Sw {
+            rs2,
+            imm,
+            paren_rs1,
+        } if is_creg(rs2) && is_creg(paren_rs1) && fits(imm, 7, 4, false) => AstNode::CSw {
+            rs2,
+            imm,
+            paren_rs1,
+        },
+        AstNode::Fsw {
+            frs2,
+            imm,
+            paren_rs1: 2,
+        } if xlen() == 32 && fits(imm, 8, 4, false) => AstNode::CFswsp {
+            frs2,
+            imm,
+            paren_rs1: 2,
+        },
+        AstNode::Fsw {
+            frs2,
+            imm,
+            paren_rs1,
+        } if xlen() == 32 && is_creg(frs2) && is_creg(paren_rs1) && fits(imm, 7, 4, false) => {
+            AstNode::CFsw {
+                frs2,
+                imm,
+                paren_rs1,
+            }
+        }
+        AstNode::Sd {
+            rs2,
+            imm,
+            paren_rs1: 2,
+        } if fits(imm, 9, 8, false) => AstNode::CSdsp {
+            rs2,
+            imm,
+            paren_rs1: 2,
+        },
+        AstNode::Sd {
+            rs2,
+            imm,
+            paren_rs1,
+        } if is_creg(rs2) && is_creg(paren_rs1) && fits(imm, 8, 8, false) => AstNode::CSd {
+            rs2,
+            imm,
+            paren_rs1,
+        },
+        AstNode::Fsd {
+            frs2,
+            imm,
+            paren_rs1: 2,
+        } if fits(imm, 9, 8, false) => AstNode::CFsdsp {
+            frs2,
+            imm,
+            paren_rs1: 2,
+        },
+        AstNode::Fsd {
+            frs2,
+            imm,
+            paren_rs1,
+        } if is_creg(frs2) && is_creg(paren_rs1) && fits(imm, 8, 8, false) => AstNode::CFsd {
+            frs2,
+            imm,
+            paren_rs1,
+        },
+
+        AstNode::Ebreak {} => AstNode::CEbreak {},
+
+        AstNode::Jalr { rd: 0, imm: 0, rs1 } if rs1 != 0 => AstNode::CJr { rs1 },
+        AstNode::Jalr { rd: 1, imm: 0, rs1 } if rs1 != 0 => AstNode::CJalr { rs1 },
+
+        _ => return None,
+    };
+
+    Some(compressed)
+}
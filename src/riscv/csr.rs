@@ -0,0 +1,166 @@
+// Names accepted in place of a CSR number.
+
+use super::isa::Extension;
+
+const CSRS: &[(&str, u32)] = &[
+    // Unprivileged floating point
+    ("fflags", 0x001),
+    ("frm", 0x002),
+    ("fcsr", 0x003),
+    // Unprivileged vector
+    ("vstart", 0x008),
+    ("vxsat", 0x009),
+    ("vxrm", 0x00A),
+    ("vcsr", 0x00F),
+    ("vl", 0xC20),
+    ("vtype", 0xC21),
+    ("vlenb", 0xC22),
+    // Unprivileged counters
+    ("cycle", 0xC00),
+    ("time", 0xC01),
+    ("instret", 0xC02),
+    ("cycleh", 0xC80),
+    ("timeh", 0xC81),
+    ("instreth", 0xC82),
+    // Supervisor
+    ("sstatus", 0x100),
+    ("sie", 0x104),
+    ("stvec", 0x105),
+    ("scounteren", 0x106),
+    ("senvcfg", 0x10A),
+    ("sscratch", 0x140),
+    ("sepc", 0x141),
+    ("scause", 0x142),
+    ("stval", 0x143),
+    ("sip", 0x144),
+    ("satp", 0x180),
+    ("scontext", 0x5A8),
+    // Machine information
+    ("mvendorid", 0xF11),
+    ("marchid", 0xF12),
+    ("mimpid", 0xF13),
+    ("mhartid", 0xF14),
+    ("mconfigptr", 0xF15),
+    // Machine trap setup and handling
+    ("mstatus", 0x300),
+    ("misa", 0x301),
+    ("medeleg", 0x302),
+    ("mideleg", 0x303),
+    ("mie", 0x304),
+    ("mtvec", 0x305),
+    ("mcounteren", 0x306),
+    ("menvcfg", 0x30A),
+    ("mstatush", 0x310),
+    ("menvcfgh", 0x31A),
+    ("mcountinhibit", 0x320),
+    ("mscratch", 0x340),
+    ("mepc", 0x341),
+    ("mcause", 0x342),
+    ("mtval", 0x343),
+    ("mip", 0x344),
+    ("mseccfg", 0x747),
+    // Machine counters
+    ("mcycle", 0xB00),
+    ("minstret", 0xB02),
+    ("mcycleh", 0xB80),
+    ("minstreth", 0xB82),
+    // Debug and trigger
+    ("tselect", 0x7A0),
+    ("tdata1", 0x7A1),
+    ("tdata2", 0x7A2),
+    ("tdata3", 0x7A3),
+    ("mcontext", 0x7A8),
+    ("dcsr", 0x7B0),
+    ("dpc", 0x7B1),
+    ("dscratch0", 0x7B2),
+    ("dscratch1", 0x7B3),
+];
+
+/// CSRs added by the hypervisor extension.
+const HYPERVISOR_CSRS: &[(&str, u32)] = &[
+    // Machine trap handling
+    ("mtinst", 0x34A),
+    ("mtval2", 0x34B),
+    // Hypervisor trap setup and handling
+    ("hstatus", 0x600),
+    ("hedeleg", 0x602),
+    ("hideleg", 0x603),
+    ("hie", 0x604),
+    ("htimedelta", 0x605),
+    ("hcounteren", 0x606),
+    ("hgeie", 0x607),
+    ("henvcfg", 0x60A),
+    ("hedelegh", 0x612),
+    ("htimedeltah", 0x615),
+    ("henvcfgh", 0x61A),
+    ("htval", 0x643),
+    ("hip", 0x644),
+    ("hvip", 0x645),
+    ("htinst", 0x64A),
+    ("hgeip", 0xE12),
+    ("hgatp", 0x680),
+    ("hcontext", 0x6A8),
+    // Virtual supervisor
+    ("vsstatus", 0x200),
+    ("vsie", 0x204),
+    ("vstvec", 0x205),
+    ("vsscratch", 0x240),
+    ("vsepc", 0x241),
+    ("vscause", 0x242),
+    ("vstval", 0x243),
+    ("vsip", 0x244),
+    ("vsatp", 0x280),
+    ("vstimecmp", 0x24D),
+    ("vstimecmph", 0x25D),
+];
+
+/// Numbered CSRs, `prefix`, an index in `first..=last` and `suffix`, numbered from `base`.
+const NUMBERED_CSRS: &[(&str, &str, u32, u32, u32)] = &[
+    ("hpmcounter", "", 3, 31, 0xC00),
+    ("hpmcounter", "h", 3, 31, 0xC80),
+    ("mhpmcounter", "", 3, 31, 0xB00),
+    ("mhpmcounter", "h", 3, 31, 0xB80),
+    ("mhpmevent", "", 3, 31, 0x320),
+    ("pmpcfg", "", 0, 15, 0x3A0),
+    ("pmpaddr", "", 0, 63, 0x3B0),
+];
+
+pub fn csr_number(name: &str) -> Option<u32> {
+    if let Some((_, number)) = CSRS
+        .iter()
+        .chain(HYPERVISOR_CSRS)
+        .find(|(csr, _)| *csr == name)
+    {
+        return Some(*number);
+    }
+
+    NUMBERED_CSRS
+        .iter()
+        .find_map(|(prefix, suffix, first, last, base)| {
+            let index = name.strip_prefix(prefix)?.strip_suffix(suffix)?;
+            let n = index.parse::<u32>().ok()?;
+
+            // reject leading zeros and signs, `pmpcfg01` or `pmpcfg+1`
+            (n.to_string() == index && (*first..=*last).contains(&n)).then_some(base + n)
+        })
+}
+
+/// Extension a named CSR belongs to, `None` when it is always available.
+pub fn csr_extension(name: &str) -> Option<Extension> {
+    HYPERVISOR_CSRS
+        .iter()
+        .any(|(csr, _)| *csr == name)
+        .then_some(Extension::H)
+}
+
+/// XLEN a named CSR is limited to, the upper halves of 64-bit CSRs only exist on RV32.
+pub fn csr_xlen(name: &str) -> Option<u32> {
+    csr_number(name)
+        .filter(|number| {
+            matches!(
+                number,
+                0xC80..=0xC9F | 0xB80..=0xB9F | 0x310 | 0x31A | 0x612 | 0x615 | 0x61A | 0x25D
+            )
+        })
+        .map(|_| 32)
+}
@@ -0,0 +1,263 @@
+// Instructions described in the `--isa-ext` spec file. Each one picks a `.insn`
+// format, fixes some of its fields and takes the rest as operands, in TOML:
+//
+//     [[instruction]]
+//     mnemonic = "cx.mac"
+//     format = "r"
+//     opcode = "CUSTOM_0"
+//     fields = { funct3 = 0, funct7 = 1 }
+//     operands = ["rd", "rs1", "rs2"]
+//
+// or the same layout in JSON when the file ends in `.json`.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs::read_to_string,
+    path::Path,
+    sync::OnceLock,
+};
+
+use logos::Logos;
+use serde::Deserialize;
+use thiserror::Error;
+
+use super::insn::{Insn, format_fields, is_valid_field, is_valid_opcode, opcode_number};
+use crate::parser::token::Token;
+
+#[derive(Error, Debug)]
+pub enum CustomIsaError {
+    #[error("Can't read `{0}`: {1}")]
+    Read(String, std::io::Error),
+
+    #[error("Invalid instruction spec `{0}`: {1}")]
+    Toml(String, toml::de::Error),
+
+    #[error("Invalid instruction spec `{0}`: {1}")]
+    Json(String, serde_json::Error),
+
+    #[error("`{0}` can't be used as a mnemonic, it is already an instruction or not a name")]
+    Mnemonic(String),
+
+    #[error("`{0}` is defined twice")]
+    Duplicate(String),
+
+    #[error("`{0}` has unknown format `{1}`")]
+    UnknownFormat(String, String),
+
+    #[error("`{0}` has unknown opcode `{1}`")]
+    UnknownOpcode(String, String),
+
+    #[error("`{0}` has opcode {1:#x}, which isn't a `{2}` opcode")]
+    InvalidOpcode(String, u32, String),
+
+    #[error("`{0}` has unknown operand `{1}`")]
+    UnknownOperand(String, String),
+
+    #[error("`{0}` sets `{1}`, which isn't a field of its format")]
+    UnknownField(String, String),
+
+    #[error("`{0}` sets `{1}` to {2}, which doesn't fit the field")]
+    InvalidField(String, String, u64),
+
+    #[error("`{0}` sets `{1}` more than once")]
+    DuplicateField(String, String),
+
+    #[error("`{0}` doesn't set `{1}`, fix it in `fields` or add it to `operands`")]
+    MissingField(String, String),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Spec {
+    #[serde(rename = "instruction", default)]
+    instructions: Vec<InstructionSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InstructionSpec {
+    mnemonic: String,
+    format: String,
+    opcode: Opcode,
+    #[serde(default)]
+    fields: HashMap<String, u64>,
+    #[serde(default)]
+    operands: Vec<String>,
+}
+
+/// Major opcode, a number or a name like `CUSTOM_0`.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Opcode {
+    Number(u32),
+    Name(String),
+}
+
+/// Operands a spec can take and the field each one fills, named like the
+/// arguments of the built-in instructions.
+const OPERANDS: &[(&str, &str)] = &[
+    ("rd", "rd"),
+    ("rs1", "rs1"),
+    ("rs2", "rs2"),
+    ("rs3", "rs3"),
+    ("frd", "rd"),
+    ("frs1", "rs1"),
+    ("frs2", "rs2"),
+    ("frs3", "rs3"),
+    ("paren_rs1", "rs1"),
+    ("imm", "imm"),
+    ("symbol", "symbol"),
+];
+
+fn operand_field(operand: &str) -> Option<&'static str> {
+    OPERANDS
+        .iter()
+        .find(|(name, _)| *name == operand)
+        .map(|(_, field)| *field)
+}
+
+#[derive(Debug)]
+pub struct CustomInsn {
+    format: String,
+    opcode: u32,
+    fields: HashMap<String, u64>,
+    pub operands: Vec<String>,
+}
+
+impl CustomInsn {
+    /// Instruction with the fixed fields and the `values` of each operand, `None`
+    /// if the spec doesn't name a format.
+    pub fn insn(&self, values: &HashMap<&str, u64>, symbol: String) -> Option<Insn> {
+        let field = |name: &str| {
+            self.fields
+                .get(name)
+                .copied()
+                .or_else(|| {
+                    values
+                        .iter()
+                        .find(|(operand, _)| operand_field(operand) == Some(name))
+                        .map(|(_, value)| *value)
+                })
+                .unwrap_or_default()
+        };
+
+        Insn::from_fields(&self.format, self.opcode, field, symbol)
+    }
+}
+
+static CUSTOM_INSNS: OnceLock<HashMap<String, CustomInsn>> = OnceLock::new();
+
+pub fn custom_insn(mnemonic: &str) -> Option<&'static CustomInsn> {
+    CUSTOM_INSNS.get()?.get(mnemonic)
+}
+
+/// Loads the instructions of the spec file at `path`.
+pub fn load_isa_ext(path: &str) -> Result<(), CustomIsaError> {
+    let source = read_to_string(path).map_err(|err| CustomIsaError::Read(path.to_string(), err))?;
+
+    let spec: Spec = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str(&source)
+            .map_err(|err| CustomIsaError::Json(path.to_string(), err))?,
+        _ => toml::from_str(&source).map_err(|err| CustomIsaError::Toml(path.to_string(), err))?,
+    };
+
+    let mut insns = HashMap::new();
+
+    for spec in spec.instructions {
+        let mnemonic = spec.mnemonic.clone();
+        let insn = check_spec(spec)?;
+
+        if insns.insert(mnemonic.clone(), insn).is_some() {
+            return Err(CustomIsaError::Duplicate(mnemonic));
+        }
+    }
+
+    CUSTOM_INSNS.set(insns).unwrap();
+
+    Ok(())
+}
+
+fn check_spec(spec: InstructionSpec) -> Result<CustomInsn, CustomIsaError> {
+    let mnemonic = spec.mnemonic;
+
+    if !is_free_mnemonic(&mnemonic) {
+        return Err(CustomIsaError::Mnemonic(mnemonic));
+    }
+
+    let Some(format_fields) = format_fields(&spec.format) else {
+        return Err(CustomIsaError::UnknownFormat(mnemonic, spec.format));
+    };
+
+    let opcode = match spec.opcode {
+        Opcode::Number(opcode) => opcode,
+        Opcode::Name(name) => match opcode_number(&name) {
+            Some(opcode) => opcode,
+            None => return Err(CustomIsaError::UnknownOpcode(mnemonic, name)),
+        },
+    };
+
+    if !is_valid_opcode(&spec.format, opcode) {
+        return Err(CustomIsaError::InvalidOpcode(mnemonic, opcode, spec.format));
+    }
+
+    let mut set = HashSet::new();
+
+    for (field, value) in &spec.fields {
+        if !format_fields.contains(&field.as_str()) {
+            return Err(CustomIsaError::UnknownField(mnemonic, field.clone()));
+        }
+
+        if !is_valid_field(&spec.format, field, *value) {
+            return Err(CustomIsaError::InvalidField(mnemonic, field.clone(), *value));
+        }
+
+        set.insert(field.as_str());
+    }
+
+    for operand in &spec.operands {
+        let Some(field) = operand_field(operand) else {
+            return Err(CustomIsaError::UnknownOperand(mnemonic, operand.clone()));
+        };
+
+        if !format_fields.contains(&field) {
+            return Err(CustomIsaError::UnknownField(mnemonic, field.to_string()));
+        }
+
+        if !set.insert(field) {
+            return Err(CustomIsaError::DuplicateField(mnemonic, field.to_string()));
+        }
+    }
+
+    if let Some(field) = format_fields.iter().find(|field| !set.contains(*field)) {
+        return Err(CustomIsaError::MissingField(mnemonic, field.to_string()));
+    }
+
+    Ok(CustomInsn {
+        format: spec.format,
+        opcode,
+        fields: spec.fields,
+        operands: spec.operands,
+    })
+}
+
+/// Whether `mnemonic` lexes as a plain name, `cx.mac` comes out as an
+/// identifier followed by names, so it can't shadow a built-in instruction.
+fn is_free_mnemonic(mnemonic: &str) -> bool {
+    let mut lex = Token::lexer(mnemonic);
+    let mut len = 0;
+
+    while let Some(token) = lex.next() {
+        let expected = match len {
+            0 => matches!(token, Ok(Token::Identifier(_))),
+            _ => matches!(token, Ok(Token::Name(_))),
+        };
+
+        if !expected || lex.span().start != len {
+            return false;
+        }
+
+        len = lex.span().end;
+    }
+
+    len != 0 && len == mnemonic.len()
+}
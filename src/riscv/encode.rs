@@ -1,5 +1,3 @@
-use crate::riscv::PC;
-
 pub struct ImmArgs {
     pub imm: u64,
     pub rs1: u32,
@@ -17,6 +15,16 @@ pub struct RegArgs {
     pub opcode: u32,
 }
 
+pub struct R4Args {
+    pub rs3: u32,
+    pub funct2: u32,
+    pub rs2: u32,
+    pub rs1: u32,
+    pub rd: u32,
+    pub funct3: u32,
+    pub opcode: u32,
+}
+
 pub struct StoreArgs {
     pub imm: u64,
     pub rs2: u32,
@@ -25,12 +33,93 @@ pub struct StoreArgs {
     pub funct3: u32,
 }
 
+pub struct BranchArgs {
+    pub imm: u64,
+    pub rs2: u32,
+    pub rs1: u32,
+    pub funct3: u32,
+    pub opcode: u32,
+}
+
+pub struct JumpArgs {
+    pub imm: u64,
+    pub rd: u32,
+    pub opcode: u32,
+}
+
 pub struct UpperArgs {
     pub imm: u64,
     pub rd: u32,
     pub opcode: u32,
 }
 
+// Compressed formats, `imm` holds the immediate bits already placed with `scatter`.
+
+pub struct CrArgs {
+    pub funct4: u32,
+    pub rd: u32,
+    pub rs2: u32,
+    pub opcode: u32,
+}
+
+pub struct CiArgs {
+    pub funct3: u32,
+    pub imm: u32,
+    pub rd: u32,
+    pub opcode: u32,
+}
+
+pub struct CssArgs {
+    pub funct3: u32,
+    pub imm: u32,
+    pub rs2: u32,
+    pub opcode: u32,
+}
+
+pub struct CiwArgs {
+    pub funct3: u32,
+    pub imm: u32,
+    pub rd: u32,
+    pub opcode: u32,
+}
+
+pub struct ClArgs {
+    pub funct3: u32,
+    pub imm: u32,
+    pub rs1: u32,
+    pub rd: u32,
+    pub opcode: u32,
+}
+
+pub struct CsArgs {
+    pub funct3: u32,
+    pub imm: u32,
+    pub rs1: u32,
+    pub rs2: u32,
+    pub opcode: u32,
+}
+
+pub struct CaArgs {
+    pub funct6: u32,
+    pub rd: u32,
+    pub funct2: u32,
+    pub rs2: u32,
+    pub opcode: u32,
+}
+
+pub struct CbArgs {
+    pub funct3: u32,
+    pub imm: u32,
+    pub rs1: u32,
+    pub opcode: u32,
+}
+
+pub struct CjArgs {
+    pub funct3: u32,
+    pub imm: u32,
+    pub opcode: u32,
+}
+
 // 00000000_00000000_00000000_00000000_00000000_00000000_00000000_00000000
 
 pub fn upper(arg: UpperArgs) -> Vec<u8> {
@@ -39,8 +128,19 @@ pub fn upper(arg: UpperArgs) -> Vec<u8> {
     ins.to_le_bytes().to_vec()
 }
 
+pub fn jump(arg: JumpArgs) -> Vec<u8> {
+    let ins = ((arg.imm & 0x100000) as u32) << 11
+        | ((arg.imm & 0x0007FE) as u32) << 20
+        | ((arg.imm & 0x000800) as u32) << 9
+        | (arg.imm & 0x0FF000) as u32
+        | arg.rd << 7
+        | arg.opcode;
+
+    ins.to_le_bytes().to_vec()
+}
+
 pub fn store(arg: StoreArgs) -> Vec<u8> {
-    let ins = ((arg.imm & 0x0000000000000FE0) as u32) << 25
+    let ins = ((arg.imm & 0x0000000000000FE0) as u32) << 20
         | arg.rs2 << 20
         | arg.rs1 << 15
         | arg.funct3 << 12
@@ -50,11 +150,24 @@ pub fn store(arg: StoreArgs) -> Vec<u8> {
     ins.to_le_bytes().to_vec()
 }
 
+pub fn branch(arg: BranchArgs) -> Vec<u8> {
+    let ins = ((arg.imm & 0x1000) as u32) << 19
+        | ((arg.imm & 0x07E0) as u32) << 20
+        | arg.rs2 << 20
+        | arg.rs1 << 15
+        | arg.funct3 << 12
+        | ((arg.imm & 0x001E) as u32) << 7
+        | ((arg.imm & 0x0800) as u32) >> 4
+        | arg.opcode;
+
+    ins.to_le_bytes().to_vec()
+}
+
 pub fn immediate(arg: ImmArgs) -> Vec<u8> {
     let ins = (((arg.imm & 0x0fff) as u32) << 20)
         | arg.rs1 << 15
         | arg.funct3 << 12
-        | (arg.rd as u32) << 7
+        | arg.rd << 7
         | arg.opcode;
 
     ins.to_le_bytes().to_vec()
@@ -70,3 +183,79 @@ pub fn register(arg: RegArgs) -> Vec<u8> {
 
     ins.to_le_bytes().to_vec()
 }
+
+pub fn register4(arg: R4Args) -> Vec<u8> {
+    let ins = arg.rs3 << 27
+        | arg.funct2 << 25
+        | arg.rs2 << 20
+        | arg.rs1 << 15
+        | arg.funct3 << 12
+        | arg.rd << 7
+        | arg.opcode;
+
+    ins.to_le_bytes().to_vec()
+}
+
+/// Moves each `(hi, lo, to)` range `imm[hi:lo]` to start at bit `to`.
+pub fn scatter(imm: u64, fields: &[(u32, u32, u32)]) -> u32 {
+    fields.iter().fold(0, |ins, &(hi, lo, to)| {
+        let mask = (1 << (hi - lo + 1)) - 1;
+
+        ins | ((imm >> lo) as u32 & mask) << to
+    })
+}
+
+pub fn cr(arg: CrArgs) -> Vec<u8> {
+    let ins = (arg.funct4 << 12 | arg.rd << 7 | arg.rs2 << 2 | arg.opcode) as u16;
+
+    ins.to_le_bytes().to_vec()
+}
+
+pub fn ci(arg: CiArgs) -> Vec<u8> {
+    let ins = (arg.funct3 << 13 | arg.imm | arg.rd << 7 | arg.opcode) as u16;
+
+    ins.to_le_bytes().to_vec()
+}
+
+pub fn css(arg: CssArgs) -> Vec<u8> {
+    let ins = (arg.funct3 << 13 | arg.imm | arg.rs2 << 2 | arg.opcode) as u16;
+
+    ins.to_le_bytes().to_vec()
+}
+
+pub fn ciw(arg: CiwArgs) -> Vec<u8> {
+    let ins = (arg.funct3 << 13 | arg.imm | arg.rd << 2 | arg.opcode) as u16;
+
+    ins.to_le_bytes().to_vec()
+}
+
+pub fn cl(arg: ClArgs) -> Vec<u8> {
+    let ins = (arg.funct3 << 13 | arg.imm | arg.rs1 << 7 | arg.rd << 2 | arg.opcode) as u16;
+
+    ins.to_le_bytes().to_vec()
+}
+
+pub fn cs(arg: CsArgs) -> Vec<u8> {
+    let ins = (arg.funct3 << 13 | arg.imm | arg.rs1 << 7 | arg.rs2 << 2 | arg.opcode) as u16;
+
+    ins.to_le_bytes().to_vec()
+}
+
+pub fn ca(arg: CaArgs) -> Vec<u8> {
+    let ins =
+        (arg.funct6 << 10 | arg.rd << 7 | arg.funct2 << 5 | arg.rs2 << 2 | arg.opcode) as u16;
+
+    ins.to_le_bytes().to_vec()
+}
+
+pub fn cb(arg: CbArgs) -> Vec<u8> {
+    let ins = (arg.funct3 << 13 | arg.imm | arg.rs1 << 7 | arg.opcode) as u16;
+
+    ins.to_le_bytes().to_vec()
+}
+
+pub fn cj(arg: CjArgs) -> Vec<u8> {
+    let ins = (arg.funct3 << 13 | arg.imm | arg.opcode) as u16;
+
+    ins.to_le_bytes().to_vec()
+}
@@ -0,0 +1,635 @@
+// `.insn`, instructions written as their raw fields in the GNU as formats, for
+// encodings the assembler doesn't know about.
+
+use object::{
+    elf::{R_RISCV_BRANCH, R_RISCV_JAL, R_RISCV_RVC_BRANCH, R_RISCV_RVC_JUMP},
+    write::SectionId,
+};
+
+use super::{
+    EncodeError, cimm, creg,
+    encode::{
+        BranchArgs, CaArgs, CbArgs, CiArgs, CiwArgs, CjArgs, ClArgs, CrArgs, CsArgs, CssArgs,
+        ImmArgs, JumpArgs, R4Args, RegArgs, StoreArgs, UpperArgs, branch, ca, cb, ci, ciw, cj, cl,
+        cr, cs, css, immediate, jump, register, register4, scatter, store, upper,
+    },
+    fits, pc_relative,
+};
+use crate::elf::obj::Elf;
+
+/// Fields of an `.insn`, one variant per format.
+#[derive(Clone, Debug)]
+pub enum Insn {
+    R {
+        opcode: u32,
+        funct3: u32,
+        funct7: u32,
+        rd: u32,
+        rs1: u32,
+        rs2: u32,
+    },
+    R4 {
+        opcode: u32,
+        funct3: u32,
+        funct2: u32,
+        rd: u32,
+        rs1: u32,
+        rs2: u32,
+        rs3: u32,
+    },
+    I {
+        opcode: u32,
+        funct3: u32,
+        rd: u32,
+        rs1: u32,
+        imm: u64,
+    },
+    S {
+        opcode: u32,
+        funct3: u32,
+        rs2: u32,
+        rs1: u32,
+        imm: u64,
+    },
+    B {
+        opcode: u32,
+        funct3: u32,
+        rs1: u32,
+        rs2: u32,
+        symbol: String,
+    },
+    U {
+        opcode: u32,
+        rd: u32,
+        imm: u64,
+    },
+    J {
+        opcode: u32,
+        rd: u32,
+        symbol: String,
+    },
+    Cr {
+        opcode: u32,
+        funct4: u32,
+        rd: u32,
+        rs2: u32,
+    },
+    Ci {
+        opcode: u32,
+        funct3: u32,
+        rd: u32,
+        imm: u64,
+    },
+    Ciw {
+        opcode: u32,
+        funct3: u32,
+        rd: u32,
+        imm: u64,
+    },
+    Css {
+        opcode: u32,
+        funct3: u32,
+        rs2: u32,
+        imm: u64,
+    },
+    Cl {
+        opcode: u32,
+        funct3: u32,
+        rd: u32,
+        rs1: u32,
+        imm: u64,
+    },
+    Cs {
+        opcode: u32,
+        funct3: u32,
+        rs2: u32,
+        rs1: u32,
+        imm: u64,
+    },
+    Ca {
+        opcode: u32,
+        funct6: u32,
+        funct2: u32,
+        rd: u32,
+        rs2: u32,
+    },
+    Cb {
+        opcode: u32,
+        funct3: u32,
+        rs1: u32,
+        symbol: String,
+    },
+    Cj {
+        opcode: u32,
+        funct3: u32,
+        symbol: String,
+    },
+    /// `.insn 0x13` or `.insn 4, 0x13`
+    Raw {
+        value: u64,
+        len: u64,
+    },
+}
+
+/// Major opcodes accepted by name in place of the opcode number.
+const OPCODES: &[(&str, u32)] = &[
+    ("LOAD", 0x03),
+    ("LOAD_FP", 0x07),
+    ("CUSTOM_0", 0x0B),
+    ("MISC_MEM", 0x0F),
+    ("OP_IMM", 0x13),
+    ("AUIPC", 0x17),
+    ("OP_IMM_32", 0x1B),
+    ("STORE", 0x23),
+    ("STORE_FP", 0x27),
+    ("CUSTOM_1", 0x2B),
+    ("AMO", 0x2F),
+    ("OP", 0x33),
+    ("LUI", 0x37),
+    ("OP_32", 0x3B),
+    ("MADD", 0x43),
+    ("MSUB", 0x47),
+    ("NMSUB", 0x4B),
+    ("NMADD", 0x4F),
+    ("OP_FP", 0x53),
+    ("OP_V", 0x57),
+    ("CUSTOM_2", 0x5B),
+    ("BRANCH", 0x63),
+    ("JALR", 0x67),
+    ("JAL", 0x6F),
+    ("SYSTEM", 0x73),
+    ("CUSTOM_3", 0x7B),
+    ("C0", 0x0),
+    ("C1", 0x1),
+    ("C2", 0x2),
+];
+
+pub fn opcode_number(name: &str) -> Option<u32> {
+    OPCODES
+        .iter()
+        .find(|(opcode, _)| *opcode == name)
+        .map(|(_, number)| *number)
+}
+
+/// Fields of each format besides the opcode, in the order `.insn` takes them.
+pub const FORMATS: &[(&str, &[&str])] = &[
+    ("r", &["funct3", "funct7", "rd", "rs1", "rs2"]),
+    ("r4", &["funct3", "funct2", "rd", "rs1", "rs2", "rs3"]),
+    ("i", &["funct3", "rd", "rs1", "imm"]),
+    ("s", &["funct3", "rs2", "rs1", "imm"]),
+    ("b", &["funct3", "rs1", "rs2", "symbol"]),
+    ("sb", &["funct3", "rs1", "rs2", "symbol"]),
+    ("u", &["rd", "imm"]),
+    ("j", &["rd", "symbol"]),
+    ("uj", &["rd", "symbol"]),
+    ("cr", &["funct4", "rd", "rs2"]),
+    ("ci", &["funct3", "rd", "imm"]),
+    ("ciw", &["funct3", "rd", "imm"]),
+    ("css", &["funct3", "rs2", "imm"]),
+    ("cl", &["funct3", "rd", "rs1", "imm"]),
+    ("cs", &["funct3", "rs2", "rs1", "imm"]),
+    ("ca", &["funct6", "funct2", "rd", "rs2"]),
+    ("cb", &["funct3", "rs1", "symbol"]),
+    ("cj", &["funct3", "symbol"]),
+];
+
+pub fn format_fields(format: &str) -> Option<&'static [&'static str]> {
+    FORMATS
+        .iter()
+        .find(|(name, _)| *name == format)
+        .map(|(_, fields)| *fields)
+}
+
+/// Whether `opcode` suits `format`, the low two bits are `11` for the 32 bit
+/// formats and anything else for the compressed ones.
+pub fn is_valid_opcode(format: &str, opcode: u32) -> bool {
+    if format.starts_with('c') {
+        opcode < 0b11
+    } else {
+        opcode & 0b11 == 0b11 && opcode >> 7 == 0
+    }
+}
+
+/// Whether `value` fits the field `name` of `format`, the 3 bit register fields
+/// of the compressed formats only take `x8`-`x15`.
+pub fn is_valid_field(format: &str, name: &str, value: u64) -> bool {
+    match (format, name) {
+        (_, "funct2") => value >> 2 == 0,
+        (_, "funct3") => value >> 3 == 0,
+        (_, "funct4") => value >> 4 == 0,
+        (_, "funct6") => value >> 6 == 0,
+        (_, "funct7") => value >> 7 == 0,
+        ("ciw", "rd")
+        | ("cl", "rd" | "rs1")
+        | ("cs", "rs1" | "rs2")
+        | ("ca", "rd" | "rs2")
+        | ("cb", "rs1") => (8..16).contains(&value),
+        (_, "rd" | "rs1" | "rs2" | "rs3") => value >> 5 == 0,
+        ("i" | "s", "imm") => fits(value, 12, 1, true),
+        ("u", "imm") => fits(value, 20, 1, false),
+        ("ci", "imm") => fits(value, 6, 1, true),
+        ("ciw", "imm") => fits(value, 8, 1, false),
+        ("css", "imm") => fits(value, 6, 1, false),
+        ("cl" | "cs", "imm") => fits(value, 5, 1, false),
+        _ => false,
+    }
+}
+
+impl Insn {
+    /// Builds an instruction of `format` from the values of its named fields.
+    pub fn from_fields<F>(format: &str, opcode: u32, field: F, symbol: String) -> Option<Self>
+    where
+        F: Fn(&str) -> u64,
+    {
+        let num = |name: &str| field(name) as u32;
+
+        let insn = match format {
+            "r" => Insn::R {
+                opcode,
+                funct3: num("funct3"),
+                funct7: num("funct7"),
+                rd: num("rd"),
+                rs1: num("rs1"),
+                rs2: num("rs2"),
+            },
+            "r4" => Insn::R4 {
+                opcode,
+                funct3: num("funct3"),
+                funct2: num("funct2"),
+                rd: num("rd"),
+                rs1: num("rs1"),
+                rs2: num("rs2"),
+                rs3: num("rs3"),
+            },
+            "i" => Insn::I {
+                opcode,
+                funct3: num("funct3"),
+                rd: num("rd"),
+                rs1: num("rs1"),
+                imm: field("imm"),
+            },
+            "s" => Insn::S {
+                opcode,
+                funct3: num("funct3"),
+                rs2: num("rs2"),
+                rs1: num("rs1"),
+                imm: field("imm"),
+            },
+            "b" | "sb" => Insn::B {
+                opcode,
+                funct3: num("funct3"),
+                rs1: num("rs1"),
+                rs2: num("rs2"),
+                symbol,
+            },
+            "u" => Insn::U {
+                opcode,
+                rd: num("rd"),
+                imm: field("imm"),
+            },
+            "j" | "uj" => Insn::J {
+                opcode,
+                rd: num("rd"),
+                symbol,
+            },
+            "cr" => Insn::Cr {
+                opcode,
+                funct4: num("funct4"),
+                rd: num("rd"),
+                rs2: num("rs2"),
+            },
+            "ci" => Insn::Ci {
+                opcode,
+                funct3: num("funct3"),
+                rd: num("rd"),
+                imm: field("imm"),
+            },
+            "ciw" => Insn::Ciw {
+                opcode,
+                funct3: num("funct3"),
+                rd: num("rd"),
+                imm: field("imm"),
+            },
+            "css" => Insn::Css {
+                opcode,
+                funct3: num("funct3"),
+                rs2: num("rs2"),
+                imm: field("imm"),
+            },
+            "cl" => Insn::Cl {
+                opcode,
+                funct3: num("funct3"),
+                rd: num("rd"),
+                rs1: num("rs1"),
+                imm: field("imm"),
+            },
+            "cs" => Insn::Cs {
+                opcode,
+                funct3: num("funct3"),
+                rs2: num("rs2"),
+                rs1: num("rs1"),
+                imm: field("imm"),
+            },
+            "ca" => Insn::Ca {
+                opcode,
+                funct6: num("funct6"),
+                funct2: num("funct2"),
+                rd: num("rd"),
+                rs2: num("rs2"),
+            },
+            "cb" => Insn::Cb {
+                opcode,
+                funct3: num("funct3"),
+                rs1: num("rs1"),
+                symbol,
+            },
+            "cj" => Insn::Cj {
+                opcode,
+                funct3: num("funct3"),
+                symbol,
+            },
+            _ => return None,
+        };
+
+        Some(insn)
+    }
+
+    pub fn size(&self) -> u64 {
+        match self {
+            Insn::Raw { len, .. } => *len,
+            Insn::Cr { .. }
+            | Insn::Ci { .. }
+            | Insn::Ciw { .. }
+            | Insn::Css { .. }
+            | Insn::Cl { .. }
+            | Insn::Cs { .. }
+            | Insn::Ca { .. }
+            | Insn::Cb { .. }
+            | Insn::Cj { .. } => 2,
+            _ => 4,
+        }
+    }
+}
+
+/// Checks that a user supplied field fits in `bits` bits.
+fn field(value: u32, bits: u32) -> Result<u32, EncodeError> {
+    if value >> bits != 0 {
+        return Err(EncodeError::Immediate(value as i64));
+    }
+
+    Ok(value)
+}
+
+/// Checks a 32 bit major opcode, its low two bits must be set.
+fn opcode(value: u32) -> Result<u32, EncodeError> {
+    if value & 0b11 != 0b11 {
+        return Err(EncodeError::Immediate(value as i64));
+    }
+
+    field(value, 7)
+}
+
+/// Checks a compressed opcode, `0b11` would make it a 32 bit instruction.
+fn c_opcode(value: u32) -> Result<u32, EncodeError> {
+    if value >= 0b11 {
+        return Err(EncodeError::Immediate(value as i64));
+    }
+
+    Ok(value)
+}
+
+pub fn encode_insn(
+    insn: &Insn,
+    elf: &mut Elf,
+    section_id: SectionId,
+    pc: u64,
+) -> Result<Vec<u8>, EncodeError> {
+    let bytes = match *insn {
+        Insn::R {
+            opcode: op,
+            funct3,
+            funct7,
+            rd,
+            rs1,
+            rs2,
+        } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: field(funct7, 7)?,
+            funct3: field(funct3, 3)?,
+            opcode: opcode(op)?,
+        }),
+
+        Insn::R4 {
+            opcode: op,
+            funct3,
+            funct2,
+            rd,
+            rs1,
+            rs2,
+            rs3,
+        } => register4(R4Args {
+            rs3,
+            funct2: field(funct2, 2)?,
+            rs2,
+            rs1,
+            rd,
+            funct3: field(funct3, 3)?,
+            opcode: opcode(op)?,
+        }),
+
+        Insn::I {
+            opcode: op,
+            funct3,
+            rd,
+            rs1,
+            imm,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1,
+            rd,
+            funct3: field(funct3, 3)?,
+            opcode: opcode(op)?,
+        }),
+
+        Insn::S {
+            opcode: op,
+            funct3,
+            rs2,
+            rs1,
+            imm,
+        } => store(StoreArgs {
+            rs1,
+            rs2,
+            funct3: field(funct3, 3)?,
+            imm: cimm(imm, 12, 1, true)?,
+            opcode: opcode(op)?,
+        }),
+
+        Insn::B {
+            opcode: op,
+            funct3,
+            rs1,
+            rs2,
+            ref symbol,
+        } => branch(BranchArgs {
+            imm: pc_relative(elf, section_id, pc, symbol, R_RISCV_BRANCH)?,
+            rs1,
+            rs2,
+            funct3: field(funct3, 3)?,
+            opcode: opcode(op)?,
+        }),
+
+        Insn::U {
+            opcode: op,
+            rd,
+            imm,
+        } => upper(UpperArgs {
+            imm: cimm(imm, 20, 1, false)?,
+            rd,
+            opcode: opcode(op)?,
+        }),
+
+        Insn::J {
+            opcode: op,
+            rd,
+            ref symbol,
+        } => jump(JumpArgs {
+            imm: pc_relative(elf, section_id, pc, symbol, R_RISCV_JAL)?,
+            rd,
+            opcode: opcode(op)?,
+        }),
+
+        Insn::Cr {
+            opcode,
+            funct4,
+            rd,
+            rs2,
+        } => cr(CrArgs {
+            funct4: field(funct4, 4)?,
+            rd,
+            rs2,
+            opcode: c_opcode(opcode)?,
+        }),
+
+        Insn::Ci {
+            opcode,
+            funct3,
+            rd,
+            imm,
+        } => ci(CiArgs {
+            funct3: field(funct3, 3)?,
+            imm: scatter(cimm(imm, 6, 1, true)?, &[(5, 5, 12), (4, 0, 2)]),
+            rd,
+            opcode: c_opcode(opcode)?,
+        }),
+
+        Insn::Ciw {
+            opcode,
+            funct3,
+            rd,
+            imm,
+        } => ciw(CiwArgs {
+            funct3: field(funct3, 3)?,
+            imm: scatter(cimm(imm, 8, 1, false)?, &[(7, 0, 5)]),
+            rd: creg(rd)?,
+            opcode: c_opcode(opcode)?,
+        }),
+
+        Insn::Css {
+            opcode,
+            funct3,
+            rs2,
+            imm,
+        } => css(CssArgs {
+            funct3: field(funct3, 3)?,
+            imm: scatter(cimm(imm, 6, 1, false)?, &[(5, 0, 7)]),
+            rs2,
+            opcode: c_opcode(opcode)?,
+        }),
+
+        Insn::Cl {
+            opcode,
+            funct3,
+            rd,
+            rs1,
+            imm,
+        } => cl(ClArgs {
+            funct3: field(funct3, 3)?,
+            imm: scatter(cimm(imm, 5, 1, false)?, &[(4, 2, 10), (1, 0, 5)]),
+            rs1: creg(rs1)?,
+            rd: creg(rd)?,
+            opcode: c_opcode(opcode)?,
+        }),
+
+        Insn::Cs {
+            opcode,
+            funct3,
+            rs2,
+            rs1,
+            imm,
+        } => cs(CsArgs {
+            funct3: field(funct3, 3)?,
+            imm: scatter(cimm(imm, 5, 1, false)?, &[(4, 2, 10), (1, 0, 5)]),
+            rs1: creg(rs1)?,
+            rs2: creg(rs2)?,
+            opcode: c_opcode(opcode)?,
+        }),
+
+        Insn::Ca {
+            opcode,
+            funct6,
+            funct2,
+            rd,
+            rs2,
+        } => ca(CaArgs {
+            funct6: field(funct6, 6)?,
+            rd: creg(rd)?,
+            funct2: field(funct2, 2)?,
+            rs2: creg(rs2)?,
+            opcode: c_opcode(opcode)?,
+        }),
+
+        Insn::Cb {
+            opcode,
+            funct3,
+            rs1,
+            ref symbol,
+        } => cb(CbArgs {
+            funct3: field(funct3, 3)?,
+            imm: scatter(
+                pc_relative(elf, section_id, pc, symbol, R_RISCV_RVC_BRANCH)?,
+                &[(8, 8, 12), (4, 3, 10), (7, 6, 5), (2, 1, 3), (5, 5, 2)],
+            ),
+            rs1: creg(rs1)?,
+            opcode: c_opcode(opcode)?,
+        }),
+
+        Insn::Cj {
+            opcode,
+            funct3,
+            ref symbol,
+        } => cj(CjArgs {
+            funct3: field(funct3, 3)?,
+            imm: scatter(
+                pc_relative(elf, section_id, pc, symbol, R_RISCV_RVC_JUMP)?,
+                &[
+                    (11, 11, 12),
+                    (4, 4, 11),
+                    (9, 8, 9),
+                    (10, 10, 8),
+                    (6, 6, 7),
+                    (7, 7, 6),
+                    (3, 1, 3),
+                    (5, 5, 2),
+                ],
+            ),
+            opcode: c_opcode(opcode)?,
+        }),
+
+        Insn::Raw { value, len } => value.to_le_bytes()[..len as usize].to_vec(),
+    };
+
+    Ok(bytes)
+}
@@ -0,0 +1,980 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+use thiserror::Error;
+
+use crate::parser::token::Token;
+
+#[derive(Error, Debug)]
+pub enum IsaError {
+    #[error("Invalid ISA string `{0}`, it must start with rv32 or rv64 and the i, e or g base")]
+    InvalidBase(String),
+
+    #[error("Unknown extension `{0}` in ISA string")]
+    UnknownExtension(String),
+
+    #[error("`{0}` can't change the register width of the target")]
+    XlenChange(String),
+
+    #[error("Unknown ABI `{0}`")]
+    UnknownAbi(String),
+
+    #[error("ABI `{0}` doesn't match the RV{1} target")]
+    AbiXlen(&'static str, u32),
+
+    #[error("ABI `{0}` requires extension {1}")]
+    AbiExtension(&'static str, &'static str),
+
+    #[error("ABI `{0}` can't be used with the E base ISA, only ilp32e and lp64e can")]
+    AbiEmbedded(&'static str),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Extension {
+    M,
+    A,
+    F,
+    D,
+    C,
+    Zicsr,
+    Zifencei,
+    Zba,
+    Zbb,
+    Zbc,
+    Zbs,
+    V,
+    Zbkb,
+    Zbkc,
+    Zbkx,
+    Zknd,
+    Zkne,
+    Zknh,
+    Zksed,
+    Zksh,
+    Zicond,
+    Zihintpause,
+    Zihintntl,
+    Zicbom,
+    Zicboz,
+    Zicbop,
+    Zfa,
+    Zacas,
+    Zfh,
+    Zfhmin,
+    Q,
+    H,
+    Ztso,
+}
+
+impl Extension {
+    pub fn name(self) -> &'static str {
+        match self {
+            Extension::M => "M",
+            Extension::A => "A",
+            Extension::F => "F",
+            Extension::D => "D",
+            Extension::C => "C",
+            Extension::Zicsr => "Zicsr",
+            Extension::Zifencei => "Zifencei",
+            Extension::Zba => "Zba",
+            Extension::Zbb => "Zbb",
+            Extension::Zbc => "Zbc",
+            Extension::Zbs => "Zbs",
+            Extension::V => "V",
+            Extension::Zbkb => "Zbkb",
+            Extension::Zbkc => "Zbkc",
+            Extension::Zbkx => "Zbkx",
+            Extension::Zknd => "Zknd",
+            Extension::Zkne => "Zkne",
+            Extension::Zknh => "Zknh",
+            Extension::Zksed => "Zksed",
+            Extension::Zksh => "Zksh",
+            Extension::Zicond => "Zicond",
+            Extension::Zihintpause => "Zihintpause",
+            Extension::Zihintntl => "Zihintntl",
+            Extension::Zicbom => "Zicbom",
+            Extension::Zicboz => "Zicboz",
+            Extension::Zicbop => "Zicbop",
+            Extension::Zfa => "Zfa",
+            Extension::Zacas => "Zacas",
+            Extension::Zfh => "Zfh",
+            Extension::Zfhmin => "Zfhmin",
+            Extension::Q => "Q",
+            Extension::H => "H",
+            Extension::Ztso => "Ztso",
+        }
+    }
+
+    const fn bit(self) -> u64 {
+        1 << self as u64
+    }
+
+    /// Version written after the name in canonical ISA strings.
+    fn version(self) -> &'static str {
+        match self {
+            Extension::A => "2p1",
+            Extension::F | Extension::D | Extension::Q => "2p2",
+            Extension::M
+            | Extension::C
+            | Extension::Zicsr
+            | Extension::Zifencei
+            | Extension::Zihintpause => "2p0",
+            _ => "1p0",
+        }
+    }
+
+    /// Extensions this one depends on, enabled along with it.
+    fn implies(self) -> &'static [Extension] {
+        match self {
+            Extension::F => &[Extension::Zicsr],
+            Extension::D | Extension::Zfhmin | Extension::Zfa => &[Extension::F],
+            Extension::Q | Extension::V => &[Extension::D],
+            Extension::Zfh => &[Extension::Zfhmin],
+            Extension::Zacas => &[Extension::A],
+            Extension::H => &[Extension::Zicsr],
+            _ => &[],
+        }
+    }
+}
+
+const ALL_EXTENSIONS: &[Extension] = &[
+    Extension::M,
+    Extension::A,
+    Extension::F,
+    Extension::D,
+    Extension::C,
+    Extension::Zicsr,
+    Extension::Zifencei,
+    Extension::Zba,
+    Extension::Zbb,
+    Extension::Zbc,
+    Extension::Zbs,
+    Extension::V,
+    Extension::Zbkb,
+    Extension::Zbkc,
+    Extension::Zbkx,
+    Extension::Zknd,
+    Extension::Zkne,
+    Extension::Zknh,
+    Extension::Zksed,
+    Extension::Zksh,
+    Extension::Zicond,
+    Extension::Zihintpause,
+    Extension::Zihintntl,
+    Extension::Zicbom,
+    Extension::Zicboz,
+    Extension::Zicbop,
+    Extension::Zfa,
+    Extension::Zacas,
+    Extension::Zfh,
+    Extension::Zfhmin,
+    Extension::Q,
+    Extension::H,
+    Extension::Ztso,
+];
+
+/// Names in ISA strings standing for several extensions.
+const EXTENSION_GROUPS: &[(&str, &[Extension])] = &[
+    (
+        "g",
+        &[
+            Extension::M,
+            Extension::A,
+            Extension::F,
+            Extension::D,
+            Extension::Zicsr,
+            Extension::Zifencei,
+        ],
+    ),
+    ("b", &[Extension::Zba, Extension::Zbb, Extension::Zbs]),
+    (
+        "zkn",
+        &[
+            Extension::Zbkb,
+            Extension::Zbkc,
+            Extension::Zbkx,
+            Extension::Zkne,
+            Extension::Zknd,
+            Extension::Zknh,
+        ],
+    ),
+    (
+        "zks",
+        &[
+            Extension::Zbkb,
+            Extension::Zbkc,
+            Extension::Zbkx,
+            Extension::Zksed,
+            Extension::Zksh,
+        ],
+    ),
+    (
+        "zk",
+        &[
+            Extension::Zbkb,
+            Extension::Zbkc,
+            Extension::Zbkx,
+            Extension::Zkne,
+            Extension::Zknd,
+            Extension::Zknh,
+        ],
+    ),
+];
+
+/// Extensions accepted in ISA strings that add no instructions to the assembler.
+const EXTENSIONS_WITHOUT_INSTRUCTIONS: &[&str] = &[
+    "zicntr", "zihpm", "zkr", "zkt", "ziccif", "ziccrse", "ziccamoa", "zicclsm", "za64rs",
+    "zic64b",
+];
+
+/// Profiles and the ISA string they stand for, limited to the extensions known here.
+const PROFILES: &[(&str, &str)] = &[
+    ("rvi20u32", "rv32i"),
+    ("rvi20u64", "rv64i"),
+    ("rva20u64", "rv64imafdc_zicsr_zicntr_ziccif_ziccrse_ziccamoa_zicclsm"),
+    (
+        "rva22u64",
+        "rv64imafdc_zicsr_zicntr_zihpm_ziccif_ziccrse_ziccamoa_zicclsm_za64rs_zihintpause\
+         _zba_zbb_zbs_zic64b_zicbom_zicbop_zicboz_zfhmin_zkt",
+    ),
+    (
+        "rva23u64",
+        "rv64imafdcv_zicsr_zicntr_zihpm_ziccif_ziccrse_ziccamoa_zicclsm_za64rs_zihintpause\
+         _zba_zbb_zbs_zic64b_zicbom_zicbop_zicboz_zfhmin_zkt_zihintntl_zicond_zfa",
+    ),
+];
+
+/// Extensions of `rv64gc`, the target until `--march` says otherwise, like GNU as.
+const DEFAULT_EXTENSIONS: u64 = Extension::M.bit()
+    | Extension::A.bit()
+    | Extension::F.bit()
+    | Extension::D.bit()
+    | Extension::C.bit()
+    | Extension::Zicsr.bit()
+    | Extension::Zifencei.bit();
+
+/// Extensions enabled for the target, one bit per `Extension`.
+static EXTENSIONS: AtomicU64 = AtomicU64::new(DEFAULT_EXTENSIONS);
+
+/// Every extension enabled at some point of the file, `.option` only adds to it.
+static USED_EXTENSIONS: AtomicU64 = AtomicU64::new(DEFAULT_EXTENSIONS);
+
+pub fn is_enabled(ext: Extension) -> bool {
+    EXTENSIONS.load(Ordering::Relaxed) & ext.bit() != 0
+}
+
+pub fn enable(ext: Extension) {
+    EXTENSIONS.fetch_or(ext.bit(), Ordering::SeqCst);
+    USED_EXTENSIONS.fetch_or(ext.bit(), Ordering::SeqCst);
+}
+
+pub fn is_used(ext: Extension) -> bool {
+    USED_EXTENSIONS.load(Ordering::Relaxed) & ext.bit() != 0
+}
+
+pub fn disable(ext: Extension) {
+    EXTENSIONS.fetch_and(!ext.bit(), Ordering::SeqCst);
+}
+
+/// Width of the integer registers of the target, 32 or 64.
+static XLEN: AtomicU32 = AtomicU32::new(64);
+
+pub fn xlen() -> u32 {
+    XLEN.load(Ordering::Relaxed)
+}
+
+/// Whether the target uses the E base ISA, with only `x0`-`x15`.
+static EMBEDDED: AtomicBool = AtomicBool::new(false);
+
+pub fn is_embedded() -> bool {
+    EMBEDDED.load(Ordering::Relaxed)
+}
+
+/// Selects the target from an ISA string like `rv64imafdc_zicsr_zba`, `rv32ec`
+/// or a profile like `rva22u64`.
+pub fn set_march(march: &str) -> Result<(), IsaError> {
+    let (xlen, embedded, extensions) = parse_march(march)?;
+
+    XLEN.store(xlen, Ordering::SeqCst);
+    EMBEDDED.store(embedded, Ordering::SeqCst);
+    EXTENSIONS.store(extensions, Ordering::SeqCst);
+    USED_EXTENSIONS.store(extensions, Ordering::SeqCst);
+
+    Ok(())
+}
+
+/// Applies `.option arch`, a `+zba` or `-c` change or a full ISA string.
+pub fn set_option_arch(arch: &str) -> Result<(), IsaError> {
+    if let Some(name) = arch.strip_prefix('+') {
+        EXTENSIONS.fetch_or(with_implied(extension_bits(name)?), Ordering::SeqCst);
+    } else if let Some(name) = arch.strip_prefix('-') {
+        EXTENSIONS.fetch_and(!extension_bits(name)?, Ordering::SeqCst);
+    } else {
+        let (xlen, embedded, extensions) = parse_march(arch)?;
+
+        if xlen != self::xlen() || embedded != is_embedded() {
+            return Err(IsaError::XlenChange(arch.to_string()));
+        }
+
+        EXTENSIONS.store(extensions, Ordering::SeqCst);
+    }
+
+    USED_EXTENSIONS.fetch_or(EXTENSIONS.load(Ordering::SeqCst), Ordering::SeqCst);
+
+    Ok(())
+}
+
+/// Register width, E base and enabled extensions of an ISA string.
+fn parse_march(march: &str) -> Result<(u32, bool, u64), IsaError> {
+    let march = march.to_ascii_lowercase();
+    let march = match PROFILES.iter().find(|(name, _)| march.starts_with(name)) {
+        Some((name, isa)) => format!("{isa}{}", &march[name.len()..]),
+        None => march,
+    };
+
+    let xlen = match march.get(..4) {
+        Some("rv32") => 32,
+        Some("rv64") => 64,
+        _ => return Err(IsaError::InvalidBase(march)),
+    };
+
+    let (embedded, mut extensions) = match march.get(4..5) {
+        Some("i") => (false, 0),
+        Some("e") => (true, 0),
+        Some("g") => (false, extension_bits("g")?),
+        _ => return Err(IsaError::InvalidBase(march)),
+    };
+
+    for (i, part) in skip_version(&march[5..]).split('_').enumerate() {
+        if i > 0 && part.starts_with(['z', 's', 'x']) {
+            extensions |= extension_bits(strip_version(part))?;
+
+            continue;
+        }
+
+        // single letter extensions, each with an optional version like `m2p0`
+        let mut letters = part;
+        while let Some(letter) = letters.get(..1) {
+            extensions |= extension_bits(letter)?;
+
+            letters = skip_version(&letters[1..]);
+        }
+    }
+
+    Ok((xlen, embedded, with_implied(extensions)))
+}
+
+/// Skips the version following a single letter extension, `2p0` in `m2p0`.
+fn skip_version(letters: &str) -> &str {
+    let letters = letters.trim_start_matches(|c: char| c.is_ascii_digit());
+
+    match letters.strip_prefix('p') {
+        Some(minor) if minor.starts_with(|c: char| c.is_ascii_digit()) => {
+            minor.trim_start_matches(|c: char| c.is_ascii_digit())
+        }
+        _ => letters,
+    }
+}
+
+/// Drops the version of a multi letter extension, `zba1p0`.
+fn strip_version(name: &str) -> &str {
+    if !name.ends_with(|c: char| c.is_ascii_digit()) {
+        return name;
+    }
+
+    let name = name.trim_end_matches(|c: char| c.is_ascii_digit());
+
+    match name.strip_suffix('p') {
+        Some(major) if major.ends_with(|c: char| c.is_ascii_digit()) => {
+            major.trim_end_matches(|c: char| c.is_ascii_digit())
+        }
+        _ => name,
+    }
+}
+
+/// Bits of the extensions an ISA string name stands for.
+fn extension_bits(name: &str) -> Result<u64, IsaError> {
+    if let Some(ext) = ALL_EXTENSIONS
+        .iter()
+        .find(|ext| ext.name().eq_ignore_ascii_case(name))
+    {
+        return Ok(ext.bit());
+    }
+
+    if let Some((_, exts)) = EXTENSION_GROUPS.iter().find(|(group, _)| *group == name) {
+        return Ok(exts.iter().fold(0, |bits, ext| bits | ext.bit()));
+    }
+
+    if EXTENSIONS_WITHOUT_INSTRUCTIONS.contains(&name) {
+        return Ok(0);
+    }
+
+    Err(IsaError::UnknownExtension(name.to_string()))
+}
+
+/// Adds the extensions implied by the ones in `bits`.
+fn with_implied(mut bits: u64) -> u64 {
+    loop {
+        let implied = ALL_EXTENSIONS
+            .iter()
+            .filter(|ext| bits & ext.bit() != 0)
+            .flat_map(|ext| ext.implies())
+            .fold(bits, |bits, ext| bits | ext.bit());
+
+        if implied == bits {
+            return bits;
+        }
+
+        bits = implied;
+    }
+}
+
+/// Floating point registers used to pass arguments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FloatAbi {
+    Soft,
+    Single,
+    Double,
+    Quad,
+}
+
+/// Calling convention of the object, `lp64d` or `ilp32e`.
+#[derive(Clone, Copy, Debug)]
+pub struct Abi {
+    pub name: &'static str,
+    pub xlen: u32,
+    pub float: FloatAbi,
+    pub embedded: bool,
+}
+
+const ABIS: &[Abi] = &[
+    Abi::new("ilp32", 32, FloatAbi::Soft, false),
+    Abi::new("ilp32f", 32, FloatAbi::Single, false),
+    Abi::new("ilp32d", 32, FloatAbi::Double, false),
+    Abi::new("ilp32q", 32, FloatAbi::Quad, false),
+    Abi::new("ilp32e", 32, FloatAbi::Soft, true),
+    Abi::new("lp64", 64, FloatAbi::Soft, false),
+    Abi::new("lp64f", 64, FloatAbi::Single, false),
+    Abi::new("lp64d", 64, FloatAbi::Double, false),
+    Abi::new("lp64q", 64, FloatAbi::Quad, false),
+    Abi::new("lp64e", 64, FloatAbi::Soft, true),
+];
+
+impl Abi {
+    const fn new(name: &'static str, xlen: u32, float: FloatAbi, embedded: bool) -> Self {
+        Self {
+            name,
+            xlen,
+            float,
+            embedded,
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self, IsaError> {
+        ABIS.iter()
+            .find(|abi| abi.name == name)
+            .copied()
+            .ok_or_else(|| IsaError::UnknownAbi(name.to_string()))
+    }
+
+    /// ABI GCC picks for the target when none is given.
+    pub fn for_target() -> Self {
+        let float = if is_embedded() {
+            FloatAbi::Soft
+        } else if is_enabled(Extension::D) {
+            FloatAbi::Double
+        } else if is_enabled(Extension::F) {
+            FloatAbi::Single
+        } else {
+            FloatAbi::Soft
+        };
+
+        *ABIS
+            .iter()
+            .find(|abi| abi.xlen == xlen() && abi.float == float && abi.embedded == is_embedded())
+            .unwrap()
+    }
+
+    /// Checks that the target ISA has the registers the ABI passes arguments in.
+    pub fn check(self) -> Result<Self, IsaError> {
+        if self.xlen != xlen() {
+            return Err(IsaError::AbiXlen(self.name, xlen()));
+        }
+
+        if is_embedded() && !self.embedded {
+            return Err(IsaError::AbiEmbedded(self.name));
+        }
+
+        let required = match self.float {
+            FloatAbi::Soft => None,
+            FloatAbi::Single => Some(Extension::F),
+            FloatAbi::Double => Some(Extension::D),
+            FloatAbi::Quad => Some(Extension::Q),
+        };
+
+        match required {
+            Some(ext) if !is_enabled(ext) => Err(IsaError::AbiExtension(self.name, ext.name())),
+            _ => Ok(self),
+        }
+    }
+}
+
+/// Canonical ISA string of the target with every version, `rv64i2p1_m2p0_c2p0`.
+pub fn arch_string() -> String {
+    isa_string(EXTENSIONS.load(Ordering::Relaxed))
+}
+
+/// ISA string of every extension used in the file, see `USED_EXTENSIONS`.
+pub fn used_arch_string() -> String {
+    isa_string(USED_EXTENSIONS.load(Ordering::Relaxed))
+}
+
+fn isa_string(bits: u64) -> String {
+    let mut exts = ALL_EXTENSIONS
+        .iter()
+        .copied()
+        .filter(|ext| bits & ext.bit() != 0)
+        .collect::<Vec<_>>();
+    exts.sort_by_key(|ext| canonical_order(*ext));
+
+    let base = if is_embedded() { "e2p0" } else { "i2p1" };
+    let mut isa = format!("rv{}{base}", xlen());
+
+    for ext in exts {
+        isa.push('_');
+        isa.push_str(&ext.name().to_ascii_lowercase());
+        isa.push_str(ext.version());
+    }
+
+    isa
+}
+
+/// Position in canonical ISA strings, single letters in `imafdqlcbkjtpvh` order
+/// and then multi letter extensions grouped by the letter after `z`.
+fn canonical_order(ext: Extension) -> (usize, usize, String) {
+    const ORDER: &str = "imafdqlcbkjtpvh";
+
+    let name = ext.name().to_ascii_lowercase();
+    let rank = |letter: char| ORDER.find(letter).unwrap_or(ORDER.len());
+
+    match name.strip_prefix('z').and_then(|rest| rest.chars().next()) {
+        Some(category) => (1, rank(category), name),
+        None => (0, rank(name.chars().next().unwrap()), name),
+    }
+}
+
+/// Register width the instruction is limited to, `None` when it exists in RV32 and RV64.
+pub fn required_xlen(token: &Token) -> Option<u32> {
+    match token {
+        Token::Ld
+        | Token::Sd
+        | Token::Lwu
+        | Token::Addiw
+        | Token::Slliw
+        | Token::Srliw
+        | Token::Sraiw
+        | Token::Addw
+        | Token::Subw
+        | Token::Sllw
+        | Token::Srlw
+        | Token::Sraw
+        | Token::Mulw
+        | Token::Divw
+        | Token::Divuw
+        | Token::Remw
+        | Token::Remuw
+        | Token::LrD(_)
+        | Token::ScD(_)
+        | Token::AmoSwapD(_)
+        | Token::AmoAddD(_)
+        | Token::AmoXorD(_)
+        | Token::AmoAndD(_)
+        | Token::AmoOrD(_)
+        | Token::AmoMinD(_)
+        | Token::AmoMaxD(_)
+        | Token::AmoMinuD(_)
+        | Token::AmoMaxuD(_)
+        | Token::AmoCasQ(_)
+        | Token::FcvtL(_)
+        | Token::FcvtLu(_)
+        | Token::FcvtFL(_)
+        | Token::FcvtFLu(_)
+        | Token::FmvXD
+        | Token::FmvDX
+        | Token::CLd
+        | Token::CSd
+        | Token::CLdsp
+        | Token::CSdsp
+        | Token::CAddiw
+        | Token::CAddw
+        | Token::CSubw
+        | Token::AddUw
+        | Token::Sh1addUw
+        | Token::Sh2addUw
+        | Token::Sh3addUw
+        | Token::SlliUw
+        | Token::ZextW
+        | Token::Clzw
+        | Token::Ctzw
+        | Token::Cpopw
+        | Token::Rolw
+        | Token::Rorw
+        | Token::Roriw
+        | Token::Packw
+        | Token::Aes64es
+        | Token::Aes64esm
+        | Token::Aes64ds
+        | Token::Aes64dsm
+        | Token::Aes64im
+        | Token::Aes64ks1i
+        | Token::Aes64ks2
+        | Token::Sha512sum0
+        | Token::Sha512sum1
+        | Token::Sha512sig0
+        | Token::Sha512sig1
+        | Token::HlvWu
+        | Token::HlvD
+        | Token::HsvD => Some(64),
+
+        Token::Aes32esi
+        | Token::Aes32esmi
+        | Token::Aes32dsi
+        | Token::Aes32dsmi
+        | Token::Sha512sum0r
+        | Token::Sha512sum1r
+        | Token::Sha512sig0l
+        | Token::Sha512sig1l
+        | Token::Sha512sig0h
+        | Token::Sha512sig1h
+        | Token::Zip
+        | Token::Unzip
+        | Token::FmvhXD
+        | Token::FmvpDX
+        | Token::CJal
+        | Token::CFlw
+        | Token::CFsw
+        | Token::CFlwsp
+        | Token::CFswsp => Some(32),
+
+        _ => None,
+    }
+}
+
+/// Extensions providing the instruction, any of them enables it. Empty for the base ISA.
+pub fn required_extension(token: &Token) -> &'static [Extension] {
+    match token {
+        Token::Mul
+        | Token::Mulh
+        | Token::Mulhsu
+        | Token::Mulhu
+        | Token::Div
+        | Token::Divu
+        | Token::Rem
+        | Token::Remu
+        | Token::Mulw
+        | Token::Divw
+        | Token::Divuw
+        | Token::Remw
+        | Token::Remuw => &[Extension::M],
+
+        Token::LrW(_)
+        | Token::ScW(_)
+        | Token::AmoSwapW(_)
+        | Token::AmoAddW(_)
+        | Token::AmoXorW(_)
+        | Token::AmoAndW(_)
+        | Token::AmoOrW(_)
+        | Token::AmoMinW(_)
+        | Token::AmoMaxW(_)
+        | Token::AmoMinuW(_)
+        | Token::AmoMaxuW(_)
+        | Token::LrD(_)
+        | Token::ScD(_)
+        | Token::AmoSwapD(_)
+        | Token::AmoAddD(_)
+        | Token::AmoXorD(_)
+        | Token::AmoAndD(_)
+        | Token::AmoOrD(_)
+        | Token::AmoMinD(_)
+        | Token::AmoMaxD(_)
+        | Token::AmoMinuD(_)
+        | Token::AmoMaxuD(_) => &[Extension::A],
+
+        Token::Flw | Token::Fsw | Token::FmvXW | Token::FmvWX => &[Extension::F],
+        Token::Fld | Token::Fsd | Token::FmvXD | Token::FmvDX => &[Extension::D],
+        Token::Flh | Token::Fsh | Token::FmvXH | Token::FmvHX => {
+            &[Extension::Zfh, Extension::Zfhmin]
+        }
+        Token::Flq | Token::Fsq => &[Extension::Q],
+        Token::FcvtFF((dest, src)) => conversion_extension(*dest, *src),
+        Token::Fmadd(fmt)
+        | Token::Fmsub(fmt)
+        | Token::Fnmsub(fmt)
+        | Token::Fnmadd(fmt)
+        | Token::Fadd(fmt)
+        | Token::Fsub(fmt)
+        | Token::Fmul(fmt)
+        | Token::Fdiv(fmt)
+        | Token::Fsqrt(fmt)
+        | Token::Fsgnj(fmt)
+        | Token::Fsgnjn(fmt)
+        | Token::Fsgnjx(fmt)
+        | Token::Fmin(fmt)
+        | Token::Fmax(fmt)
+        | Token::Feq(fmt)
+        | Token::Flt(fmt)
+        | Token::Fle(fmt)
+        | Token::Fclass(fmt)
+        | Token::FcvtW(fmt)
+        | Token::FcvtWu(fmt)
+        | Token::FcvtL(fmt)
+        | Token::FcvtLu(fmt)
+        | Token::FcvtFW(fmt)
+        | Token::FcvtFWu(fmt)
+        | Token::FcvtFL(fmt)
+        | Token::FcvtFLu(fmt)
+        | Token::Fmv(fmt)
+        | Token::Fneg(fmt)
+        | Token::Fabs(fmt) => fmt_extension(*fmt),
+
+        Token::CAddi4spn
+        | Token::CFld
+        | Token::CLw
+        | Token::CFlw
+        | Token::CLd
+        | Token::CFsd
+        | Token::CSw
+        | Token::CFsw
+        | Token::CSd
+        | Token::CNop
+        | Token::CAddi
+        | Token::CAddiw
+        | Token::CLi
+        | Token::CAddi16sp
+        | Token::CLui
+        | Token::CSrli
+        | Token::CSrai
+        | Token::CAndi
+        | Token::CSub
+        | Token::CXor
+        | Token::COr
+        | Token::CAnd
+        | Token::CSubw
+        | Token::CAddw
+        | Token::CJ
+        | Token::CJal
+        | Token::CBeqz
+        | Token::CBnez
+        | Token::CSlli
+        | Token::CFldsp
+        | Token::CLwsp
+        | Token::CFlwsp
+        | Token::CLdsp
+        | Token::CJr
+        | Token::CJalr
+        | Token::CMv
+        | Token::CAdd
+        | Token::CEbreak
+        | Token::CFsdsp
+        | Token::CSwsp
+        | Token::CFswsp
+        | Token::CSdsp => &[Extension::C],
+
+        Token::Csrrw
+        | Token::Csrrs
+        | Token::Csrrc
+        | Token::Csrrwi
+        | Token::Csrrsi
+        | Token::Csrrci
+        | Token::Csrr
+        | Token::Csrw
+        | Token::Csrs
+        | Token::Csrc
+        | Token::Csrwi
+        | Token::Csrsi
+        | Token::Csrci
+        | Token::Rdcycle
+        | Token::Rdtime
+        | Token::Rdinstret
+        | Token::Frcsr
+        | Token::Fscsr => &[Extension::Zicsr],
+
+        Token::FenceI => &[Extension::Zifencei],
+
+        Token::Sh1add
+        | Token::Sh2add
+        | Token::Sh3add
+        | Token::AddUw
+        | Token::Sh1addUw
+        | Token::Sh2addUw
+        | Token::Sh3addUw
+        | Token::SlliUw
+        | Token::ZextW => &[Extension::Zba],
+
+        Token::Min
+        | Token::Minu
+        | Token::Max
+        | Token::Maxu
+        | Token::Clz
+        | Token::Ctz
+        | Token::Cpop
+        | Token::Clzw
+        | Token::Ctzw
+        | Token::Cpopw
+        | Token::SextB
+        | Token::SextH
+        | Token::OrcB
+        | Token::ZextH => &[Extension::Zbb],
+
+        Token::Clmulr => &[Extension::Zbc],
+
+        Token::Bclr
+        | Token::Bext
+        | Token::Binv
+        | Token::Bset
+        | Token::Bclri
+        | Token::Bexti
+        | Token::Binvi
+        | Token::Bseti => &[Extension::Zbs],
+
+        Token::Andn
+        | Token::Orn
+        | Token::Xnor
+        | Token::Rol
+        | Token::Ror
+        | Token::Rolw
+        | Token::Rorw
+        | Token::Rori
+        | Token::Roriw
+        | Token::Rev8 => &[Extension::Zbb, Extension::Zbkb],
+
+        Token::Clmul | Token::Clmulh => &[Extension::Zbc, Extension::Zbkc],
+
+        Token::Pack
+        | Token::Packh
+        | Token::Packw
+        | Token::Brev8
+        | Token::Zip
+        | Token::Unzip => &[Extension::Zbkb],
+
+        Token::Xperm4 | Token::Xperm8 => &[Extension::Zbkx],
+
+        Token::Aes64ds
+        | Token::Aes64dsm
+        | Token::Aes64im
+        | Token::Aes32dsi
+        | Token::Aes32dsmi => &[Extension::Zknd],
+
+        Token::Aes64es
+        | Token::Aes64esm
+        | Token::Aes32esi
+        | Token::Aes32esmi => &[Extension::Zkne],
+
+        Token::Sha512sum0r
+        | Token::Sha512sum1r
+        | Token::Sha512sig0l
+        | Token::Sha512sig1l
+        | Token::Sha512sig0h
+        | Token::Sha512sig1h
+        | Token::Sha256sum0
+        | Token::Sha256sum1
+        | Token::Sha256sig0
+        | Token::Sha256sig1
+        | Token::Sha512sum0
+        | Token::Sha512sum1
+        | Token::Sha512sig0
+        | Token::Sha512sig1 => &[Extension::Zknh],
+
+        Token::Sm4ed | Token::Sm4ks => &[Extension::Zksed],
+
+        Token::Sm3p0 | Token::Sm3p1 => &[Extension::Zksh],
+
+        Token::Aes64ks1i | Token::Aes64ks2 => &[Extension::Zknd, Extension::Zkne],
+
+        Token::CzeroEqz | Token::CzeroNez => &[Extension::Zicond],
+
+        Token::Pause => &[Extension::Zihintpause],
+
+        Token::NtlP1
+        | Token::NtlPall
+        | Token::NtlS1
+        | Token::NtlAll
+        | Token::CNtlP1
+        | Token::CNtlPall
+        | Token::CNtlS1
+        | Token::CNtlAll => &[Extension::Zihintntl],
+
+        Token::CboClean | Token::CboFlush | Token::CboInval => &[Extension::Zicbom],
+
+        Token::CboZero => &[Extension::Zicboz],
+
+        Token::PrefetchI | Token::PrefetchR | Token::PrefetchW => &[Extension::Zicbop],
+
+        Token::Fli(_)
+        | Token::Fminm(_)
+        | Token::Fmaxm(_)
+        | Token::Fround(_)
+        | Token::Froundnx(_)
+        | Token::Fleq(_)
+        | Token::Fltq(_)
+        | Token::FcvtmodWD
+        | Token::FmvhXD
+        | Token::FmvpDX => &[Extension::Zfa],
+
+        Token::AmoCasW(_) | Token::AmoCasD(_) | Token::AmoCasQ(_) => &[Extension::Zacas],
+
+        Token::HfenceVvma
+        | Token::HfenceGvma
+        | Token::HlvB
+        | Token::HlvBu
+        | Token::HlvH
+        | Token::HlvHu
+        | Token::HlvxHu
+        | Token::HlvW
+        | Token::HlvWu
+        | Token::HlvxWu
+        | Token::HlvD
+        | Token::HsvB
+        | Token::HsvH
+        | Token::HsvW
+        | Token::HsvD => &[Extension::H],
+
+        Token::Vsetvli | Token::Vsetivli | Token::Vsetvl | Token::Vector(_) => &[Extension::V],
+
+        _ => &[],
+    }
+}
+
+/// Extension needed on top of `required_extension`, for instructions that
+/// belong to two like `c.flw`, a C instruction that only exists with F.
+pub fn also_required_extension(token: &Token) -> Option<Extension> {
+    match token {
+        Token::CFlw | Token::CFsw | Token::CFlwsp | Token::CFswsp => Some(Extension::F),
+        Token::CFld | Token::CFsd | Token::CFldsp | Token::CFsdsp => Some(Extension::D),
+
+        // `fcvt.d.h` needs D as well as Zfhmin
+        Token::FcvtFF((0b10, other) | (other, 0b10)) if *other != 0b00 => {
+            fmt_extension(*other).first().copied()
+        }
+        _ => None,
+    }
+}
+
+/// Extension providing the floating point format `fmt`.
+fn fmt_extension(fmt: u32) -> &'static [Extension] {
+    match fmt {
+        0b00 => &[Extension::F],
+        0b01 => &[Extension::D],
+        0b10 => &[Extension::Zfh],
+        _ => &[Extension::Q],
+    }
+}
+
+/// Extensions providing `fcvt` between two floating point formats, Zfhmin is
+/// enough for the half precision ones.
+fn conversion_extension(dest: u32, src: u32) -> &'static [Extension] {
+    match (dest, src) {
+        (0b10, _) | (_, 0b10) => &[Extension::Zfh, Extension::Zfhmin],
+        (0b11, _) | (_, 0b11) => &[Extension::Q],
+        _ => &[Extension::D],
+    }
+}
@@ -1,167 +1,3077 @@
 // note: apply & 0x0FFF to imm in addi
 
+pub mod compress;
+pub mod csr;
+pub mod custom;
 pub mod encode;
+pub mod insn;
+pub mod isa;
+pub mod vector;
+
+#[cfg(test)]
+mod tests;
 use std::{
     collections::HashMap,
-    path::Path,
     sync::atomic::{AtomicU64, Ordering},
 };
 
 use object::{
-    elf::{R_RISCV_HI20, R_RISCV_LO12_I}, write::{coff::Symbol, Relocation, SectionId}, SectionKind, SymbolKind
+    Architecture, SectionKind, SymbolKind,
+    elf::{
+        EF_RISCV_FLOAT_ABI_DOUBLE, EF_RISCV_FLOAT_ABI_QUAD, EF_RISCV_FLOAT_ABI_SINGLE,
+        EF_RISCV_FLOAT_ABI_SOFT, EF_RISCV_RVC, EF_RISCV_RVE, EF_RISCV_TSO, R_RISCV_BRANCH, R_RISCV_HI20, R_RISCV_JAL, R_RISCV_LO12_I, R_RISCV_RVC_BRANCH,
+        R_RISCV_RVC_JUMP,
+    },
+    write::{Relocation, SectionId},
 };
+use thiserror::Error;
 
 use crate::{
-    elf::obj::Elf,
+    elf::{
+        attributes::{
+            AttributeValue, Attributes, TAG_RISCV_ARCH, TAG_RISCV_PRIV_SPEC,
+            TAG_RISCV_PRIV_SPEC_MINOR, TAG_RISCV_PRIV_SPEC_REVISION, TAG_RISCV_STACK_ALIGN,
+            TAG_RISCV_UNALIGNED_ACCESS,
+        },
+        obj::{CreateSymbolInfo, Elf},
+    },
     parser::ast::{AstNode, SymbolInfo, Visibility},
-    riscv::encode::{RegArgs, StoreArgs, UpperArgs, register, store, upper},
+    riscv::{
+        encode::{
+            BranchArgs, CaArgs, CbArgs, CiArgs, CiwArgs, CjArgs, ClArgs, CrArgs, CsArgs, CssArgs,
+            JumpArgs, R4Args, RegArgs, StoreArgs, UpperArgs, branch, ca, cb, ci, ciw, cj, cl, cr,
+            cs, css, jump, register, register4, scatter, store, upper,
+        },
+        insn::encode_insn,
+        isa::{
+            Abi, Extension, FloatAbi, is_embedded, is_enabled, is_used, used_arch_string, xlen,
+        },
+        vector::encode_vector,
+    },
 };
 
 use self::encode::{ImmArgs, immediate};
 
-static PC: AtomicU64 = AtomicU64::new(0);
+#[derive(Error, Debug)]
+pub enum EncodeError {
+    #[error("`{0}` is {1} bytes away, out of range of the instruction")]
+    OutOfRange(String, i64),
+
+    #[error("`{0}` is {1} bytes away, targets must be 2 byte aligned")]
+    Misaligned(String, i64),
+
+    #[error("Immediate {0} out of range")]
+    Immediate(i64),
+    #[error("Register {0} can't be used here, {1}")]
+    Register(u32, &'static str),
+    #[error("`{0}` only rounds towards zero, rtz")]
+    RoundingMode(&'static str),
+}
+
+static PC: AtomicU64 = AtomicU64::new(0);
+
+const RM_RNE: u32 = 0b000;
+const RM_RTZ: u32 = 0b001;
+const RM_DYN: u32 = 0b111;
+
+pub fn encode(node: AstNode, elf: &mut Elf, section_id: SectionId) -> Result<Vec<u8>, EncodeError> {
+    let base = PC.load(Ordering::SeqCst);
+
+    let result = match node {
+        AstNode::Ecall {} => immediate(ImmArgs {
+            imm: 0x0,
+            rs1: 0x0,
+            rd: 0x0,
+            funct3: 0x0,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Ebreak {} => immediate(ImmArgs {
+            imm: 0x1,
+            rs1: 0x0,
+            rd: 0x0,
+            funct3: 0x0,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Sret {} => immediate(ImmArgs {
+            imm: 0x102,
+            rs1: 0x0,
+            rd: 0x0,
+            funct3: 0x0,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Mret {} => immediate(ImmArgs {
+            imm: 0x302,
+            rs1: 0x0,
+            rd: 0x0,
+            funct3: 0x0,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Wfi {} => immediate(ImmArgs {
+            imm: 0x105,
+            rs1: 0x0,
+            rd: 0x0,
+            funct3: 0x0,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::SfenceVma { rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd: 0x0,
+            funct7: 0b0001001,
+            funct3: 0x0,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HfenceVvma { rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd: 0x0,
+            funct7: 0b0010001,
+            funct3: 0x0,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HfenceGvma { rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd: 0x0,
+            funct7: 0b0110001,
+            funct3: 0x0,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HlvB { rd, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 0,
+            rd,
+            funct7: 0b0110000,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HlvBu { rd, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 1,
+            rd,
+            funct7: 0b0110000,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HlvH { rd, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 0,
+            rd,
+            funct7: 0b0110010,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HlvHu { rd, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 1,
+            rd,
+            funct7: 0b0110010,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HlvxHu { rd, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 3,
+            rd,
+            funct7: 0b0110010,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HlvW { rd, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 0,
+            rd,
+            funct7: 0b0110100,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HlvWu { rd, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 1,
+            rd,
+            funct7: 0b0110100,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HlvxWu { rd, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 3,
+            rd,
+            funct7: 0b0110100,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HlvD { rd, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 0,
+            rd,
+            funct7: 0b0110110,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HsvB { rs2, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd: 0x0,
+            funct7: 0b0110001,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HsvH { rs2, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd: 0x0,
+            funct7: 0b0110011,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HsvW { rs2, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd: 0x0,
+            funct7: 0b0110101,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::HsvD { rs2, paren_rs1 } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd: 0x0,
+            funct7: 0b0110111,
+            funct3: 0x4,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Fence { pred, succ } => immediate(ImmArgs {
+            imm: (pred << 4 | succ) as u64,
+            rs1: 0x0,
+            rd: 0x0,
+            funct3: 0x0,
+            opcode: 0b0001111,
+        }),
+
+        // fm = 1000, rw, rw
+        AstNode::FenceTso {} => immediate(ImmArgs {
+            imm: 0x833,
+            rs1: 0x0,
+            rd: 0x0,
+            funct3: 0x0,
+            opcode: 0b0001111,
+        }),
+
+        AstNode::FenceI {} => immediate(ImmArgs {
+            imm: 0x0,
+            rs1: 0x0,
+            rd: 0x0,
+            funct3: 0x1,
+            opcode: 0b0001111,
+        }),
+
+        AstNode::CzeroEqz { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x7,
+            funct3: 0x5,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::CzeroNez { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x7,
+            funct3: 0x7,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::CboInval { paren_rs1 } => immediate(ImmArgs {
+            imm: 0x0,
+            rs1: paren_rs1,
+            rd: 0x0,
+            funct3: 0x2,
+            opcode: 0b0001111,
+        }),
+
+        AstNode::CboClean { paren_rs1 } => immediate(ImmArgs {
+            imm: 0x1,
+            rs1: paren_rs1,
+            rd: 0x0,
+            funct3: 0x2,
+            opcode: 0b0001111,
+        }),
+
+        AstNode::CboFlush { paren_rs1 } => immediate(ImmArgs {
+            imm: 0x2,
+            rs1: paren_rs1,
+            rd: 0x0,
+            funct3: 0x2,
+            opcode: 0b0001111,
+        }),
+
+        AstNode::CboZero { paren_rs1 } => immediate(ImmArgs {
+            imm: 0x4,
+            rs1: paren_rs1,
+            rd: 0x0,
+            funct3: 0x2,
+            opcode: 0b0001111,
+        }),
+
+        // ori x0 with the offset in imm[11:5]
+        AstNode::PrefetchI { imm, paren_rs1 } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 32, true)?,
+            rs1: paren_rs1,
+            rd: 0x0,
+            funct3: 0x6,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::PrefetchR { imm, paren_rs1 } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 32, true)? | 0b00001,
+            rs1: paren_rs1,
+            rd: 0x0,
+            funct3: 0x6,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::PrefetchW { imm, paren_rs1 } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 32, true)? | 0b00011,
+            rs1: paren_rs1,
+            rd: 0x0,
+            funct3: 0x6,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Pack { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x4,
+            funct3: 0x4,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Packh { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x4,
+            funct3: 0x7,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Packw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x4,
+            funct3: 0x4,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Xperm4 { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x14,
+            funct3: 0x2,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Xperm8 { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x14,
+            funct3: 0x4,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Aes64es { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x19,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Aes64esm { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1b,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Aes64ds { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1d,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Aes64dsm { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1f,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Aes64ks2 { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x3f,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sha512sum0r { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x28,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sha512sum1r { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x29,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sha512sig0l { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x2a,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sha512sig1l { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x2b,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sha512sig0h { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x2e,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sha512sig1h { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x2f,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Brev8 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x687,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Zip { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x8f,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Unzip { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x8f,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Aes64im { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x300,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sha256sum0 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x100,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sha256sum1 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x101,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sha256sig0 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x102,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sha256sig1 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x103,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sha512sum0 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x104,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sha512sum1 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x105,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sha512sig0 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x106,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sha512sig1 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x107,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sm3p0 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x108,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sm3p1 { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x109,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Aes64ks1i { rd, rs1, rnum } => immediate(ImmArgs {
+            imm: 0x310 | round_number(rnum)?,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Aes32esi { rd, rs1, rs2, bs } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: (cimm(bs, 2, 1, false)? as u32) << 5 | 0b10001,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Aes32esmi { rd, rs1, rs2, bs } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: (cimm(bs, 2, 1, false)? as u32) << 5 | 0b10011,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Aes32dsi { rd, rs1, rs2, bs } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: (cimm(bs, 2, 1, false)? as u32) << 5 | 0b10101,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Aes32dsmi { rd, rs1, rs2, bs } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: (cimm(bs, 2, 1, false)? as u32) << 5 | 0b10111,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sm4ed { rd, rs1, rs2, bs } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: (cimm(bs, 2, 1, false)? as u32) << 5 | 0b11000,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sm4ks { rd, rs1, rs2, bs } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: (cimm(bs, 2, 1, false)? as u32) << 5 | 0b11010,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Vsetvli { rd, rs1, vtype } => immediate(ImmArgs {
+            imm: vtype as u64,
+            rs1,
+            rd,
+            funct3: 0x7,
+            opcode: 0b1010111,
+        }),
+
+        AstNode::Vsetivli { rd, imm, vtype } => immediate(ImmArgs {
+            imm: 0xC00 | vtype as u64,
+            rs1: cimm(imm, 5, 1, false)? as u32,
+            rd,
+            funct3: 0x7,
+            opcode: 0b1010111,
+        }),
+
+        AstNode::Vsetvl { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x40,
+            funct3: 0x7,
+            opcode: 0b1010111,
+        }),
+
+        AstNode::Vector {
+            ins,
+            vd,
+            vs2,
+            vs1,
+            vm,
+        } => encode_vector(ins, vd, vs2, vs1, vm)?,
+
+        AstNode::Csrrw { rd, csr, rs1 } => immediate(ImmArgs {
+            imm: csr as u64,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Csrrs { rd, csr, rs1 } => immediate(ImmArgs {
+            imm: csr as u64,
+            rs1,
+            rd,
+            funct3: 0x2,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Csrrc { rd, csr, rs1 } => immediate(ImmArgs {
+            imm: csr as u64,
+            rs1,
+            rd,
+            funct3: 0x3,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Csrrwi { rd, csr, imm } => immediate(ImmArgs {
+            imm: csr as u64,
+            rs1: cimm(imm, 5, 1, false)? as u32,
+            rd,
+            funct3: 0x5,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Csrrsi { rd, csr, imm } => immediate(ImmArgs {
+            imm: csr as u64,
+            rs1: cimm(imm, 5, 1, false)? as u32,
+            rd,
+            funct3: 0x6,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Csrrci { rd, csr, imm } => immediate(ImmArgs {
+            imm: csr as u64,
+            rs1: cimm(imm, 5, 1, false)? as u32,
+            rd,
+            funct3: 0x7,
+            opcode: 0b1110011,
+        }),
+
+        AstNode::Addi { rd, rs1, imm } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1,
+            rd,
+            funct3: 0x0,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Slti { rd, rs1, imm } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1,
+            rd,
+            funct3: 0x2,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sltiu { rd, rs1, imm } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1,
+            rd,
+            funct3: 0x3,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Xori { rd, rs1, imm } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1,
+            rd,
+            funct3: 0x4,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Ori { rd, rs1, imm } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1,
+            rd,
+            funct3: 0x6,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Andi { rd, rs1, imm } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1,
+            rd,
+            funct3: 0x7,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Slli { rd, rs1, imm } => immediate(ImmArgs {
+            imm: xlen_shamt(imm)?,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Srli { rd, rs1, imm } => immediate(ImmArgs {
+            imm: xlen_shamt(imm)?,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Srai { rd, rs1, imm } => immediate(ImmArgs {
+            imm: 0x400 | xlen_shamt(imm)?,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Addiw { rd, rs1, imm } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1,
+            rd,
+            funct3: 0x0,
+            opcode: 0b0011011,
+        }),
+
+        AstNode::Slliw { rd, rs1, imm } => immediate(ImmArgs {
+            imm: shamt(imm, 5)?,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0011011,
+        }),
+
+        AstNode::Srliw { rd, rs1, imm } => immediate(ImmArgs {
+            imm: shamt(imm, 5)?,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0011011,
+        }),
+
+        AstNode::Sraiw { rd, rs1, imm } => immediate(ImmArgs {
+            imm: 0x400 | shamt(imm, 5)?,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0011011,
+        }),
+
+        AstNode::Addw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x0,
+            funct3: 0x0,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Subw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x20,
+            funct3: 0x0,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Sllw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x0,
+            funct3: 0x1,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Srlw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x0,
+            funct3: 0x5,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Sraw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x20,
+            funct3: 0x5,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Mul { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Mulh { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x1,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Mulhsu { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x2,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Mulhu { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x3,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Div { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x4,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Divu { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x5,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Rem { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x6,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Remu { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x7,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Mulw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x0,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Divw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x4,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Divuw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x5,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Remw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x6,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Remuw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x1,
+            funct3: 0x7,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Sh1add { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x10,
+            funct3: 0x2,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sh2add { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x10,
+            funct3: 0x4,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sh3add { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x10,
+            funct3: 0x6,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::AddUw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x4,
+            funct3: 0x0,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Sh1addUw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x10,
+            funct3: 0x2,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Sh2addUw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x10,
+            funct3: 0x4,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Sh3addUw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x10,
+            funct3: 0x6,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Andn { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x20,
+            funct3: 0x7,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Orn { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x20,
+            funct3: 0x6,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Xnor { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x20,
+            funct3: 0x4,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Min { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x5,
+            funct3: 0x4,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Minu { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x5,
+            funct3: 0x5,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Max { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x5,
+            funct3: 0x6,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Maxu { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x5,
+            funct3: 0x7,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Rol { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x30,
+            funct3: 0x1,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Ror { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x30,
+            funct3: 0x5,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Rolw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x30,
+            funct3: 0x1,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Rorw { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x30,
+            funct3: 0x5,
+            opcode: 0b0111011,
+        }),
+
+        AstNode::Clmul { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x5,
+            funct3: 0x1,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Clmulr { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x5,
+            funct3: 0x2,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Clmulh { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x5,
+            funct3: 0x3,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Bclr { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x24,
+            funct3: 0x1,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Bext { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x24,
+            funct3: 0x5,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Binv { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x34,
+            funct3: 0x1,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Bset { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x14,
+            funct3: 0x1,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::SlliUw { rd, rs1, imm } => immediate(ImmArgs {
+            imm: 0x80 | shamt(imm, 6)?,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0011011,
+        }),
+
+        AstNode::Rori { rd, rs1, imm } => immediate(ImmArgs {
+            imm: 0x600 | xlen_shamt(imm)?,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Roriw { rd, rs1, imm } => immediate(ImmArgs {
+            imm: 0x600 | shamt(imm, 5)?,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0011011,
+        }),
+
+        AstNode::Bclri { rd, rs1, imm } => immediate(ImmArgs {
+            imm: 0x480 | xlen_shamt(imm)?,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Bexti { rd, rs1, imm } => immediate(ImmArgs {
+            imm: 0x480 | xlen_shamt(imm)?,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Binvi { rd, rs1, imm } => immediate(ImmArgs {
+            imm: 0x680 | xlen_shamt(imm)?,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Bseti { rd, rs1, imm } => immediate(ImmArgs {
+            imm: 0x280 | xlen_shamt(imm)?,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Clz { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x600,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Ctz { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x601,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Cpop { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x602,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Clzw { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x600,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0011011,
+        }),
+
+        AstNode::Ctzw { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x601,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0011011,
+        }),
+
+        AstNode::Cpopw { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x602,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0011011,
+        }),
+
+        AstNode::SextB { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x604,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::SextH { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x605,
+            rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::OrcB { rd, rs1 } => immediate(ImmArgs {
+            imm: 0x287,
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Rev8 { rd, rs1 } => immediate(ImmArgs {
+            imm: if xlen() == 32 { 0x698 } else { 0x6b8 },
+            rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0010011,
+        }),
+
+        // `pack` on RV32 and `packw` on RV64
+        AstNode::ZextH { rd, rs1 } => register(RegArgs {
+            rs1,
+            rs2: 0x0,
+            rd,
+            funct7: 0x4,
+            funct3: 0x4,
+            opcode: if xlen() == 32 { 0b0110011 } else { 0b0111011 },
+        }),
+
+        AstNode::LrW {
+            aqrl,
+            rd,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 0,
+            rd,
+            funct7: 0b00010 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::ScW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b00011 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoSwapW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b00001 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoAddW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoXorW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b00100 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoAndW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b01100 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoOrW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b01000 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoMinW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b10000 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoMaxW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b10100 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoMinuW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b11000 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoMaxuW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b11100 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::LrD {
+            aqrl,
+            rd,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: 0,
+            rd,
+            funct7: 0b00010 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::ScD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b00011 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoSwapD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b00001 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoAddD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoXorD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b00100 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoAndD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b01100 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoOrD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b01000 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoMinD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b10000 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoMaxD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b10100 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoMinuD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b11000 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoMaxuD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b11100 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoCasW {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2,
+            rd,
+            funct7: 0b00101 << 2 | aqrl,
+            funct3: 0x2,
+            opcode: 0b0101111,
+        }),
+
+        // RV32 compares and swaps register pairs
+        AstNode::AmoCasD {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: if xlen() == 32 { even_reg(rs2)? } else { rs2 },
+            rd: if xlen() == 32 { even_reg(rd)? } else { rd },
+            funct7: 0b00101 << 2 | aqrl,
+            funct3: 0x3,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::AmoCasQ {
+            aqrl,
+            rd,
+            rs2,
+            paren_rs1,
+        } => register(RegArgs {
+            rs1: paren_rs1,
+            rs2: even_reg(rs2)?,
+            rd: even_reg(rd)?,
+            funct7: 0b00101 << 2 | aqrl,
+            funct3: 0x4,
+            opcode: 0b0101111,
+        }),
+
+        AstNode::Flw {
+            frd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd: frd,
+            funct3: 0x2,
+            opcode: 0b0000111,
+        }),
+
+        AstNode::Fld {
+            frd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd: frd,
+            funct3: 0x3,
+            opcode: 0b0000111,
+        }),
+
+        AstNode::Fsw {
+            frs2,
+            imm,
+            paren_rs1,
+        } => store(StoreArgs {
+            rs1: paren_rs1,
+            rs2: frs2,
+            funct3: 2,
+            imm: cimm(imm, 12, 1, true)?,
+            opcode: 0b0100111,
+        }),
+
+        AstNode::Fsd {
+            frs2,
+            imm,
+            paren_rs1,
+        } => store(StoreArgs {
+            rs1: paren_rs1,
+            rs2: frs2,
+            funct3: 3,
+            imm: cimm(imm, 12, 1, true)?,
+            opcode: 0b0100111,
+        }),
+
+        AstNode::Flh {
+            frd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd: frd,
+            funct3: 0x1,
+            opcode: 0b0000111,
+        }),
+
+        AstNode::Flq {
+            frd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd: frd,
+            funct3: 0x4,
+            opcode: 0b0000111,
+        }),
+
+        AstNode::Fsh {
+            frs2,
+            imm,
+            paren_rs1,
+        } => store(StoreArgs {
+            rs1: paren_rs1,
+            rs2: frs2,
+            funct3: 1,
+            imm: cimm(imm, 12, 1, true)?,
+            opcode: 0b0100111,
+        }),
+
+        AstNode::Fsq {
+            frs2,
+            imm,
+            paren_rs1,
+        } => store(StoreArgs {
+            rs1: paren_rs1,
+            rs2: frs2,
+            funct3: 4,
+            imm: cimm(imm, 12, 1, true)?,
+            opcode: 0b0100111,
+        }),
+
+        AstNode::Fmadd {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+            frs3,
+            rm,
+        } => register4(R4Args {
+            rs3: frs3,
+            funct2: fmt,
+            rs2: frs2,
+            rs1: frs1,
+            rd: frd,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1000011,
+        }),
+
+        AstNode::Fmsub {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+            frs3,
+            rm,
+        } => register4(R4Args {
+            rs3: frs3,
+            funct2: fmt,
+            rs2: frs2,
+            rs1: frs1,
+            rd: frd,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1000111,
+        }),
+
+        AstNode::Fnmsub {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+            frs3,
+            rm,
+        } => register4(R4Args {
+            rs3: frs3,
+            funct2: fmt,
+            rs2: frs2,
+            rs1: frs1,
+            rd: frd,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1001011,
+        }),
+
+        AstNode::Fnmadd {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+            frs3,
+            rm,
+        } => register4(R4Args {
+            rs3: frs3,
+            funct2: fmt,
+            rs2: frs2,
+            rs1: frs1,
+            rd: frd,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1001111,
+        }),
+
+        AstNode::Fadd {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+            rm,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fsub {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+            rm,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00001 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fmul {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+            rm,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00010 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fdiv {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+            rm,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00011 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fsqrt { fmt, frd, frs1, rm } => register(RegArgs {
+            rs1: frs1,
+            rs2: 0,
+            rd: frd,
+            funct7: 0b01011 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fsgnj {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00100 << 2 | fmt,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fsgnjn {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00100 << 2 | fmt,
+            funct3: 0x1,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fsgnjx {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00100 << 2 | fmt,
+            funct3: 0x2,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fmin {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00101 << 2 | fmt,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fmax {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00101 << 2 | fmt,
+            funct3: 0x1,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Feq {
+            fmt,
+            rd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd,
+            funct7: 0b10100 << 2 | fmt,
+            funct3: 0x2,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Flt {
+            fmt,
+            rd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd,
+            funct7: 0b10100 << 2 | fmt,
+            funct3: 0x1,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fle {
+            fmt,
+            rd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd,
+            funct7: 0b10100 << 2 | fmt,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fclass { fmt, rd, frs1 } => register(RegArgs {
+            rs1: frs1,
+            rs2: 0,
+            rd,
+            funct7: 0b11100 << 2 | fmt,
+            funct3: 0x1,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtW { fmt, rd, frs1, rm } => register(RegArgs {
+            rs1: frs1,
+            rs2: 0,
+            rd,
+            funct7: 0b11000 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtWu { fmt, rd, frs1, rm } => register(RegArgs {
+            rs1: frs1,
+            rs2: 1,
+            rd,
+            funct7: 0b11000 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtL { fmt, rd, frs1, rm } => register(RegArgs {
+            rs1: frs1,
+            rs2: 2,
+            rd,
+            funct7: 0b11000 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtLu { fmt, rd, frs1, rm } => register(RegArgs {
+            rs1: frs1,
+            rs2: 3,
+            rd,
+            funct7: 0b11000 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtFW { fmt, frd, rs1, rm } => register(RegArgs {
+            rs1,
+            rs2: 0,
+            rd: frd,
+            funct7: 0b11010 << 2 | fmt,
+            funct3: rm.unwrap_or(int_to_float_rm(fmt, 32)),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtFWu { fmt, frd, rs1, rm } => register(RegArgs {
+            rs1,
+            rs2: 1,
+            rd: frd,
+            funct7: 0b11010 << 2 | fmt,
+            funct3: rm.unwrap_or(int_to_float_rm(fmt, 32)),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtFL { fmt, frd, rs1, rm } => register(RegArgs {
+            rs1,
+            rs2: 2,
+            rd: frd,
+            funct7: 0b11010 << 2 | fmt,
+            funct3: rm.unwrap_or(int_to_float_rm(fmt, 64)),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtFLu { fmt, frd, rs1, rm } => register(RegArgs {
+            rs1,
+            rs2: 3,
+            rd: frd,
+            funct7: 0b11010 << 2 | fmt,
+            funct3: rm.unwrap_or(int_to_float_rm(fmt, 64)),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtFF {
+            fmts: (dest, src),
+            frd,
+            frs1,
+            rm,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: src,
+            rd: frd,
+            funct7: 0b01000 << 2 | dest,
+            funct3: rm.unwrap_or(float_to_float_rm(dest, src)),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FmvXW { rd, frs1 } => register(RegArgs {
+            rs1: frs1,
+            rs2: 0,
+            rd,
+            funct7: 0b1110000,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FmvWX { frd, rs1 } => register(RegArgs {
+            rs1,
+            rs2: 0,
+            rd: frd,
+            funct7: 0b1111000,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FmvXD { rd, frs1 } => register(RegArgs {
+            rs1: frs1,
+            rs2: 0,
+            rd,
+            funct7: 0b1110001,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FmvDX { frd, rs1 } => register(RegArgs {
+            rs1,
+            rs2: 0,
+            rd: frd,
+            funct7: 0b1111001,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FmvXH { rd, frs1 } => register(RegArgs {
+            rs1: frs1,
+            rs2: 0,
+            rd,
+            funct7: 0b1110010,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FmvHX { frd, rs1 } => register(RegArgs {
+            rs1,
+            rs2: 0,
+            rd: frd,
+            funct7: 0b1111010,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fmv { fmt, frd, frs1 } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs1,
+            rd: frd,
+            funct7: 0b00100 << 2 | fmt,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fneg { fmt, frd, frs1 } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs1,
+            rd: frd,
+            funct7: 0b00100 << 2 | fmt,
+            funct3: 0x1,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fabs { fmt, frd, frs1 } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs1,
+            rd: frd,
+            funct7: 0b00100 << 2 | fmt,
+            funct3: 0x2,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fli { fmt, frd, fli } => register(RegArgs {
+            rs1: fli,
+            rs2: 1,
+            rd: frd,
+            funct7: 0b11110 << 2 | fmt,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fminm {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00101 << 2 | fmt,
+            funct3: 0x2,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fmaxm {
+            fmt,
+            frd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd: frd,
+            funct7: 0b00101 << 2 | fmt,
+            funct3: 0x3,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fround { fmt, frd, frs1, rm } => register(RegArgs {
+            rs1: frs1,
+            rs2: 0b00100,
+            rd: frd,
+            funct7: 0b01000 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Froundnx { fmt, frd, frs1, rm } => register(RegArgs {
+            rs1: frs1,
+            rs2: 0b00101,
+            rd: frd,
+            funct7: 0b01000 << 2 | fmt,
+            funct3: rm.unwrap_or(RM_DYN),
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fleq {
+            fmt,
+            rd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd,
+            funct7: 0b10100 << 2 | fmt,
+            funct3: 0x4,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::Fltq {
+            fmt,
+            rd,
+            frs1,
+            frs2,
+        } => register(RegArgs {
+            rs1: frs1,
+            rs2: frs2,
+            rd,
+            funct7: 0b10100 << 2 | fmt,
+            funct3: 0x5,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FcvtmodWD { rd, frs1, rm } => {
+            if rm != Some(RM_RTZ) {
+                return Err(EncodeError::RoundingMode("fcvtmod.w.d"));
+            }
+
+            register(RegArgs {
+                rs1: frs1,
+                rs2: 0b01000,
+                rd,
+                funct7: 0b1100001,
+                funct3: RM_RTZ,
+                opcode: 0b1010011,
+            })
+        }
+
+        AstNode::FmvhXD { rd, frs1 } => register(RegArgs {
+            rs1: frs1,
+            rs2: 1,
+            rd,
+            funct7: 0b1110001,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::FmvpDX { frd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd: frd,
+            funct7: 0b1011001,
+            funct3: 0x0,
+            opcode: 0b1010011,
+        }),
+
+        AstNode::CAddi4spn { rd, rs1, imm } => ciw(CiwArgs {
+            funct3: 0b000,
+            imm: scatter(
+                cimm(nonzero_sp(rs1, imm)?, 10, 4, false)?,
+                &[(5, 4, 11), (9, 6, 7), (2, 2, 6), (3, 3, 5)],
+            ),
+            rd: creg(rd)?,
+            opcode: 0b00,
+        }),
+
+        AstNode::CFld { frd, imm, paren_rs1 } => cl(ClArgs {
+            funct3: 0b001,
+            imm: scatter(cimm(imm, 8, 8, false)?, &[(5, 3, 10), (7, 6, 5)]),
+            rs1: creg(paren_rs1)?,
+            rd: creg(frd)?,
+            opcode: 0b00,
+        }),
+
+        AstNode::CLw { rd, imm, paren_rs1 } => cl(ClArgs {
+            funct3: 0b010,
+            imm: scatter(cimm(imm, 7, 4, false)?, &[(5, 3, 10), (2, 2, 6), (6, 6, 5)]),
+            rs1: creg(paren_rs1)?,
+            rd: creg(rd)?,
+            opcode: 0b00,
+        }),
+
+        AstNode::CFlw { frd, imm, paren_rs1 } => cl(ClArgs {
+            funct3: 0b011,
+            imm: scatter(cimm(imm, 7, 4, false)?, &[(5, 3, 10), (2, 2, 6), (6, 6, 5)]),
+            rs1: creg(paren_rs1)?,
+            rd: creg(frd)?,
+            opcode: 0b00,
+        }),
+
+        AstNode::CLd { rd, imm, paren_rs1 } => cl(ClArgs {
+            funct3: 0b011,
+            imm: scatter(cimm(imm, 8, 8, false)?, &[(5, 3, 10), (7, 6, 5)]),
+            rs1: creg(paren_rs1)?,
+            rd: creg(rd)?,
+            opcode: 0b00,
+        }),
+
+        AstNode::CFsd { frs2, imm, paren_rs1 } => cs(CsArgs {
+            funct3: 0b101,
+            imm: scatter(cimm(imm, 8, 8, false)?, &[(5, 3, 10), (7, 6, 5)]),
+            rs1: creg(paren_rs1)?,
+            rs2: creg(frs2)?,
+            opcode: 0b00,
+        }),
+
+        AstNode::CSw { rs2, imm, paren_rs1 } => cs(CsArgs {
+            funct3: 0b110,
+            imm: scatter(cimm(imm, 7, 4, false)?, &[(5, 3, 10), (2, 2, 6), (6, 6, 5)]),
+            rs1: creg(paren_rs1)?,
+            rs2: creg(rs2)?,
+            opcode: 0b00,
+        }),
+
+        AstNode::CFsw { frs2, imm, paren_rs1 } => cs(CsArgs {
+            funct3: 0b111,
+            imm: scatter(cimm(imm, 7, 4, false)?, &[(5, 3, 10), (2, 2, 6), (6, 6, 5)]),
+            rs1: creg(paren_rs1)?,
+            rs2: creg(frs2)?,
+            opcode: 0b00,
+        }),
+
+        AstNode::CSd { rs2, imm, paren_rs1 } => cs(CsArgs {
+            funct3: 0b111,
+            imm: scatter(cimm(imm, 8, 8, false)?, &[(5, 3, 10), (7, 6, 5)]),
+            rs1: creg(paren_rs1)?,
+            rs2: creg(rs2)?,
+            opcode: 0b00,
+        }),
+
+        AstNode::CNop {} => ci(CiArgs {
+            funct3: 0b000,
+            imm: 0,
+            rd: 0,
+            opcode: 0b01,
+        }),
+
+        AstNode::CAddi { rd, imm } => ci(CiArgs {
+            funct3: 0b000,
+            imm: scatter(cimm(imm, 6, 1, true)?, &[(5, 5, 12), (4, 0, 2)]),
+            rd,
+            opcode: 0b01,
+        }),
+
+        AstNode::CAddiw { rd, imm } => ci(CiArgs {
+            funct3: 0b001,
+            imm: scatter(cimm(imm, 6, 1, true)?, &[(5, 5, 12), (4, 0, 2)]),
+            rd,
+            opcode: 0b01,
+        }),
+
+        AstNode::CLi { rd, imm } => ci(CiArgs {
+            funct3: 0b010,
+            imm: scatter(cimm(imm, 6, 1, true)?, &[(5, 5, 12), (4, 0, 2)]),
+            rd,
+            opcode: 0b01,
+        }),
+
+        AstNode::CAddi16sp { rd, imm } => ci(CiArgs {
+            funct3: 0b011,
+            imm: scatter(
+                cimm(nonzero_sp(rd, imm)?, 10, 16, true)?,
+                &[(9, 9, 12), (4, 4, 6), (6, 6, 5), (8, 7, 3), (5, 5, 2)],
+            ),
+            rd,
+            opcode: 0b01,
+        }),
+
+        AstNode::CLui { rd, imm } => ci(CiArgs {
+            funct3: 0b011,
+            imm: scatter(cimm(nonzero(lui_imm(imm))?, 6, 1, true)?, &[(5, 5, 12), (4, 0, 2)]),
+            rd: not_reg(rd, &[0, 2], "c.lui can't write x0 or sp")?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CSrli { rd, imm } => cb(CbArgs {
+            funct3: 0b100,
+            imm: scatter(xlen_shamt(imm)?, &[(5, 5, 12), (4, 0, 2)]),
+            rs1: creg(rd)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CSrai { rd, imm } => cb(CbArgs {
+            funct3: 0b100,
+            imm: 0b01 << 10 | scatter(xlen_shamt(imm)?, &[(5, 5, 12), (4, 0, 2)]),
+            rs1: creg(rd)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CAndi { rd, imm } => cb(CbArgs {
+            funct3: 0b100,
+            imm: 0b10 << 10 | scatter(cimm(imm, 6, 1, true)?, &[(5, 5, 12), (4, 0, 2)]),
+            rs1: creg(rd)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CSub { rd, rs2 } => ca(CaArgs {
+            funct6: 0b100011,
+            rd: creg(rd)?,
+            funct2: 0b00,
+            rs2: creg(rs2)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CXor { rd, rs2 } => ca(CaArgs {
+            funct6: 0b100011,
+            rd: creg(rd)?,
+            funct2: 0b01,
+            rs2: creg(rs2)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::COr { rd, rs2 } => ca(CaArgs {
+            funct6: 0b100011,
+            rd: creg(rd)?,
+            funct2: 0b10,
+            rs2: creg(rs2)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CAnd { rd, rs2 } => ca(CaArgs {
+            funct6: 0b100011,
+            rd: creg(rd)?,
+            funct2: 0b11,
+            rs2: creg(rs2)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CSubw { rd, rs2 } => ca(CaArgs {
+            funct6: 0b100111,
+            rd: creg(rd)?,
+            funct2: 0b00,
+            rs2: creg(rs2)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CAddw { rd, rs2 } => ca(CaArgs {
+            funct6: 0b100111,
+            rd: creg(rd)?,
+            funct2: 0b01,
+            rs2: creg(rs2)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CJ { ref symbol } => cj(CjArgs {
+            funct3: 0b101,
+            imm: scatter(
+                pc_relative(elf, section_id, base, symbol, R_RISCV_RVC_JUMP)?,
+                &[
+                    (11, 11, 12),
+                    (4, 4, 11),
+                    (9, 8, 9),
+                    (10, 10, 8),
+                    (6, 6, 7),
+                    (7, 7, 6),
+                    (3, 1, 3),
+                    (5, 5, 2),
+                ],
+            ),
+            opcode: 0b01,
+        }),
+
+        AstNode::CJal { ref symbol } => cj(CjArgs {
+            funct3: 0b001,
+            imm: scatter(
+                pc_relative(elf, section_id, base, symbol, R_RISCV_RVC_JUMP)?,
+                &[
+                    (11, 11, 12),
+                    (4, 4, 11),
+                    (9, 8, 9),
+                    (10, 10, 8),
+                    (6, 6, 7),
+                    (7, 7, 6),
+                    (3, 1, 3),
+                    (5, 5, 2),
+                ],
+            ),
+            opcode: 0b01,
+        }),
+
+        AstNode::CBeqz { rs1, ref symbol } => cb(CbArgs {
+            funct3: 0b110,
+            imm: scatter(
+                pc_relative(elf, section_id, base, symbol, R_RISCV_RVC_BRANCH)?,
+                &[(8, 8, 12), (4, 3, 10), (7, 6, 5), (2, 1, 3), (5, 5, 2)],
+            ),
+            rs1: creg(rs1)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CBnez { rs1, ref symbol } => cb(CbArgs {
+            funct3: 0b111,
+            imm: scatter(
+                pc_relative(elf, section_id, base, symbol, R_RISCV_RVC_BRANCH)?,
+                &[(8, 8, 12), (4, 3, 10), (7, 6, 5), (2, 1, 3), (5, 5, 2)],
+            ),
+            rs1: creg(rs1)?,
+            opcode: 0b01,
+        }),
+
+        AstNode::CSlli { rd, imm } => ci(CiArgs {
+            funct3: 0b000,
+            imm: scatter(xlen_shamt(imm)?, &[(5, 5, 12), (4, 0, 2)]),
+            rd,
+            opcode: 0b10,
+        }),
+
+        AstNode::CFldsp { frd, imm, paren_rs1 } => ci(CiArgs {
+            funct3: 0b001,
+            imm: scatter(cimm(sp_offset(paren_rs1, imm)?, 9, 8, false)?, &[(5, 5, 12), (4, 3, 5), (8, 6, 2)]),
+            rd: frd,
+            opcode: 0b10,
+        }),
+
+        AstNode::CLwsp { rd, imm, paren_rs1 } => ci(CiArgs {
+            funct3: 0b010,
+            imm: scatter(cimm(sp_offset(paren_rs1, imm)?, 8, 4, false)?, &[(5, 5, 12), (4, 2, 4), (7, 6, 2)]),
+            rd,
+            opcode: 0b10,
+        }),
+
+        AstNode::CFlwsp { frd, imm, paren_rs1 } => ci(CiArgs {
+            funct3: 0b011,
+            imm: scatter(cimm(sp_offset(paren_rs1, imm)?, 8, 4, false)?, &[(5, 5, 12), (4, 2, 4), (7, 6, 2)]),
+            rd: frd,
+            opcode: 0b10,
+        }),
+
+        AstNode::CLdsp { rd, imm, paren_rs1 } => ci(CiArgs {
+            funct3: 0b011,
+            imm: scatter(cimm(sp_offset(paren_rs1, imm)?, 9, 8, false)?, &[(5, 5, 12), (4, 3, 5), (8, 6, 2)]),
+            rd,
+            opcode: 0b10,
+        }),
+
+        AstNode::CJr { rs1 } => cr(CrArgs {
+            funct4: 0b1000,
+            rd: not_reg(rs1, &[0], "the target can't be x0")?,
+            rs2: 0,
+            opcode: 0b10,
+        }),
+
+        AstNode::CJalr { rs1 } => cr(CrArgs {
+            funct4: 0b1001,
+            rd: not_reg(rs1, &[0], "the target can't be x0")?,
+            rs2: 0,
+            opcode: 0b10,
+        }),
+
+        AstNode::CMv { rd, rs2 } => cr(CrArgs {
+            funct4: 0b1000,
+            rd,
+            rs2: not_reg(rs2, &[0], "the source can't be x0")?,
+            opcode: 0b10,
+        }),
+
+        AstNode::CAdd { rd, rs2 } => cr(CrArgs {
+            funct4: 0b1001,
+            rd,
+            rs2: not_reg(rs2, &[0], "the source can't be x0")?,
+            opcode: 0b10,
+        }),
+
+        AstNode::CEbreak {} => cr(CrArgs {
+            funct4: 0b1001,
+            rd: 0,
+            rs2: 0,
+            opcode: 0b10,
+        }),
+
+        AstNode::CFsdsp { frs2, imm, paren_rs1 } => css(CssArgs {
+            funct3: 0b101,
+            imm: scatter(cimm(sp_offset(paren_rs1, imm)?, 9, 8, false)?, &[(5, 3, 10), (8, 6, 7)]),
+            rs2: frs2,
+            opcode: 0b10,
+        }),
+
+        AstNode::CSwsp { rs2, imm, paren_rs1 } => css(CssArgs {
+            funct3: 0b110,
+            imm: scatter(cimm(sp_offset(paren_rs1, imm)?, 8, 4, false)?, &[(5, 2, 9), (7, 6, 7)]),
+            rs2,
+            opcode: 0b10,
+        }),
+
+        AstNode::CFswsp { frs2, imm, paren_rs1 } => css(CssArgs {
+            funct3: 0b111,
+            imm: scatter(cimm(sp_offset(paren_rs1, imm)?, 8, 4, false)?, &[(5, 2, 9), (7, 6, 7)]),
+            rs2: frs2,
+            opcode: 0b10,
+        }),
+
+        AstNode::CSdsp { rs2, imm, paren_rs1 } => css(CssArgs {
+            funct3: 0b111,
+            imm: scatter(cimm(sp_offset(paren_rs1, imm)?, 9, 8, false)?, &[(5, 3, 10), (8, 6, 7)]),
+            rs2,
+            opcode: 0b10,
+        }),
+
+        AstNode::Mv { rd, rs1 } => immediate(ImmArgs {
+            imm: 0,
+            rs1,
+            rd,
+            funct3: 0x0,
+            opcode: 0b0010011,
+        }),
+
+        AstNode::Sub { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x20,
+            funct3: 0x0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sll { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x0,
+            funct3: 0x1,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Srl { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x0,
+            funct3: 0x5,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sra { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x20,
+            funct3: 0x5,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Slt { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x0,
+            funct3: 0x2,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Sltu { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x0,
+            funct3: 0x3,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Xor { rd, rs1, rs2 } => register(RegArgs {
+            rd,
+            rs1,
+            rs2,
+            funct7: 0x0,
+            funct3: 0x4,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Or { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x0,
+            funct3: 0x6,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::And { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct7: 0x0,
+            funct3: 0x7,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Add { rd, rs1, rs2 } => register(RegArgs {
+            rs1,
+            rs2,
+            rd,
+            funct3: 0,
+            funct7: 0,
+            opcode: 0b0110011,
+        }),
+
+        AstNode::Lb {
+            rd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd,
+            funct3: 0x0,
+            opcode: 0b0000011,
+        }),
+
+        AstNode::Lh {
+            rd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd,
+            funct3: 0x1,
+            opcode: 0b0000011,
+        }),
+
+        AstNode::Lw {
+            rd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd,
+            funct3: 0x2,
+            opcode: 0b0000011,
+        }),
+
+        AstNode::Ld {
+            rd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd,
+            funct3: 0x3,
+            opcode: 0b0000011,
+        }),
+
+        AstNode::Lbu {
+            rd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd,
+            funct3: 0x4,
+            opcode: 0b0000011,
+        }),
+
+        AstNode::Lhu {
+            rd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd,
+            funct3: 0x5,
+            opcode: 0b0000011,
+        }),
+
+        AstNode::Lwu {
+            rd,
+            imm,
+            paren_rs1,
+        } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1: paren_rs1,
+            rd,
+            funct3: 0x6,
+            opcode: 0b0000011,
+        }),
+
+        AstNode::Sb {
+            paren_rs1,
+            rs2,
+            imm,
+        } => store(StoreArgs {
+            rs1: paren_rs1,
+            rs2,
+            funct3: 0,
+            imm: cimm(imm, 12, 1, true)?,
+            opcode: 0b0100011,
+        }),
 
-pub fn encode(node: AstNode, elf: &mut Elf, section_id: SectionId) -> Vec<u8> {
-    let base = PC.load(Ordering::SeqCst);
+        AstNode::Sh {
+            paren_rs1,
+            rs2,
+            imm,
+        } => store(StoreArgs {
+            rs1: paren_rs1,
+            rs2,
+            funct3: 1,
+            imm: cimm(imm, 12, 1, true)?,
+            opcode: 0b0100011,
+        }),
 
-    let result = match node {
-        AstNode::Ecall {} => immediate(ImmArgs {
-            imm: 0x0,
-            rs1: 0x0,
-            rd: 0x0,
-            funct3: 0x0,
-            opcode: 0b1110011,
+        AstNode::Sw {
+            paren_rs1,
+            rs2,
+            imm,
+        } => store(StoreArgs {
+            rs1: paren_rs1,
+            rs2,
+            funct3: 2,
+            imm: cimm(imm, 12, 1, true)?,
+            opcode: 0b0100011,
         }),
 
-        AstNode::Addi { rd, rs1, imm } => immediate(ImmArgs {
-            imm: imm,
-            rs1,
-            rd,
-            funct3: 0x0,
-            opcode: 0b0010011,
+        AstNode::Sd {
+            paren_rs1,
+            rs2,
+            imm,
+        } => store(StoreArgs {
+            rs1: paren_rs1,
+            rs2,
+            funct3: 3,
+            imm: cimm(imm, 12, 1, true)?,
+            opcode: 0b0100011,
         }),
 
-        AstNode::Sub { rd, rs1, rs2 } => register(RegArgs {
+        AstNode::Beq {
+            rs1,
+            rs2,
+            ref symbol,
+        } => branch(BranchArgs {
+            imm: pc_relative(elf, section_id, base, symbol, R_RISCV_BRANCH)?,
             rs1,
             rs2,
-            rd,
-            funct7: 0x20,
             funct3: 0x0,
-            opcode: 0b0110011,
+            opcode: 0b1100011,
         }),
 
-        AstNode::Sll { rd, rs1, rs2 } => register(RegArgs {
+        AstNode::Bne {
+            rs1,
+            rs2,
+            ref symbol,
+        } => branch(BranchArgs {
+            imm: pc_relative(elf, section_id, base, symbol, R_RISCV_BRANCH)?,
             rs1,
             rs2,
-            rd,
-            funct7: 0x0,
             funct3: 0x1,
-            opcode: 0b0110011,
+            opcode: 0b1100011,
         }),
 
-        AstNode::Srl { rd, rs1, rs2 } => register(RegArgs {
+        AstNode::Blt {
             rs1,
             rs2,
-            rd,
-            funct7: 0x0,
-            funct3: 0x5,
-            opcode: 0b0110011,
+            ref symbol,
+        } => branch(BranchArgs {
+            imm: pc_relative(elf, section_id, base, symbol, R_RISCV_BRANCH)?,
+            rs1,
+            rs2,
+            funct3: 0x4,
+            opcode: 0b1100011,
         }),
 
-        AstNode::Sra { rd, rs1, rs2 } => register(RegArgs {
+        AstNode::Bge {
+            rs1,
+            rs2,
+            ref symbol,
+        } => branch(BranchArgs {
+            imm: pc_relative(elf, section_id, base, symbol, R_RISCV_BRANCH)?,
             rs1,
             rs2,
-            rd,
-            funct7: 0x20,
             funct3: 0x5,
-            opcode: 0b0110011,
+            opcode: 0b1100011,
         }),
 
-        AstNode::Slt { rd, rs1, rs2 } => register(RegArgs {
+        AstNode::Bltu {
             rs1,
             rs2,
-            rd,
-            funct7: 0x0,
-            funct3: 0x2,
-            opcode: 0b0110011,
-        }),
-
-        AstNode::Sltu { rd, rs1, rs2 } => register(RegArgs {
+            ref symbol,
+        } => branch(BranchArgs {
+            imm: pc_relative(elf, section_id, base, symbol, R_RISCV_BRANCH)?,
             rs1,
             rs2,
-            rd,
-            funct7: 0x0,
-            funct3: 0x3,
-            opcode: 0b0110011,
+            funct3: 0x6,
+            opcode: 0b1100011,
         }),
 
-        AstNode::Xor { rd, rs1, rs2 } => register(RegArgs {
-            rd,
+        AstNode::Bgeu {
             rs1,
             rs2,
-            funct7: 0x0,
-            funct3: 0x4,
-            opcode: 0b0110011,
-        }),
-
-        AstNode::Add { rd, rs1, rs2 } => register(RegArgs {
+            ref symbol,
+        } => branch(BranchArgs {
+            imm: pc_relative(elf, section_id, base, symbol, R_RISCV_BRANCH)?,
             rs1,
             rs2,
+            funct3: 0x7,
+            opcode: 0b1100011,
+        }),
+
+        AstNode::Jal { rd, ref symbol } => jump(JumpArgs {
+            imm: pc_relative(elf, section_id, base, symbol, R_RISCV_JAL)?,
             rd,
-            funct3: 0,
-            funct7: 0,
-            opcode: 0b0110011,
+            opcode: 0b1101111,
         }),
 
-        AstNode::Sb {
-            paren_rs1,
-            rs2,
-            imm,
-        } => store(StoreArgs {
-            rs1: paren_rs1,
-            rs2,
-            funct3: 0,
-            imm,
-            opcode: 0b0100011,
+        AstNode::Insn { ref insn } => encode_insn(insn, elf, section_id, base)?,
+
+        AstNode::Jalr { rd, imm, rs1 } => immediate(ImmArgs {
+            imm: cimm(imm, 12, 1, true)?,
+            rs1,
+            rd,
+            funct3: 0x0,
+            opcode: 0b1100111,
         }),
 
         AstNode::Lui { rd, imm } => upper(UpperArgs {
             rd,
-            imm,
+            imm: cimm(imm, 20, 1, false)?,
             opcode: 0b0110111,
         }),
 
         AstNode::Auipc { rd, imm } => upper(UpperArgs {
             rd,
-            imm,
+            imm: cimm(imm, 20, 1, false)?,
             opcode: 0b0010111,
         }),
 
         AstNode::La { rd, ref symbol } => {
-            let sym_data = { 
-                elf.get_symbol(&symbol).unwrap().to_owned()
-            };
+            let sym_data = elf.symbol_or_undefined(symbol);
             let mut ops = Vec::new();
 
-            println!("{:x}", base);
             ops.extend(upper(UpperArgs {
                 imm: sym_data.offset,
                 rd,
                 opcode: 0b0110111,
             }));
            
-            elf.create_reallocation(sym_data.section_id, Relocation {
+            elf.create_reallocation(section_id, Relocation {
                 offset: base,
                 symbol: sym_data.symbol_id,
                 addend: 0,
                 flags: object::RelocationFlags::Elf { r_type: R_RISCV_HI20 }
-            });
- 
-            PC.fetch_add(4, Ordering::SeqCst);
-
-            let base = PC.load(Ordering::SeqCst);
-
-            println!("{:x}", base);
+            }).unwrap();
 
             ops.extend(immediate(ImmArgs {
                 imm: sym_data.offset,
@@ -171,12 +3081,12 @@ pub fn encode(node: AstNode, elf: &mut Elf, section_id: SectionId) -> Vec<u8> {
                 opcode: 0b0010011,
             }));
 
-            elf.create_reallocation(sym_data.section_id, Relocation {
-                offset: base,
+            elf.create_reallocation(section_id, Relocation {
+                offset: base + 4,
                 symbol: sym_data.symbol_id,
                 addend: 0,
                 flags: object::RelocationFlags::Elf { r_type: R_RISCV_LO12_I }
-            });
+            }).unwrap();
 
             ops
         }
@@ -186,11 +3096,334 @@ pub fn encode(node: AstNode, elf: &mut Elf, section_id: SectionId) -> Vec<u8> {
         _ => Vec::new(),
     };
 
-    if !matches!(node, AstNode::Assci { .. }) {
-        PC.fetch_add(4, Ordering::SeqCst);
+    PC.fetch_add(result.len() as u64, Ordering::SeqCst);
+
+    Ok(result)
+}
+
+/// Resolves `symbol` relative to `pc` when it is a local label of the same
+/// section, otherwise leaves a relocation of `r_type` for the linker.
+fn pc_relative(
+    elf: &mut Elf,
+    section_id: SectionId,
+    pc: u64,
+    symbol: &String,
+    r_type: u32,
+) -> Result<u64, EncodeError> {
+    let sym_data = elf.symbol_or_undefined(symbol);
+
+    if sym_data.section_id == Some(section_id)
+        && matches!(sym_data.visibility, Visibility::Local)
+        && !sym_data.weak
+    {
+        let offset = sym_data.offset.wrapping_sub(pc);
+        let bits = match r_type {
+            R_RISCV_BRANCH => 13,
+            R_RISCV_JAL => 21,
+            R_RISCV_RVC_BRANCH => 9,
+            R_RISCV_RVC_JUMP => 12,
+            _ => 64,
+        };
+
+        if !offset.is_multiple_of(2) {
+            return Err(EncodeError::Misaligned(symbol.clone(), offset as i64));
+        }
+
+        if !fits(offset, bits, 2, true) {
+            return Err(EncodeError::OutOfRange(symbol.clone(), offset as i64));
+        }
+
+        return Ok(offset);
+    }
+
+    elf.create_reallocation(section_id, Relocation {
+        offset: pc,
+        symbol: sym_data.symbol_id,
+        addend: 0,
+        flags: object::RelocationFlags::Elf { r_type }
+    }).unwrap();
+
+    Ok(0)
+}
+
+/// Significand bits of a floating point format, including the implicit one.
+fn precision(fmt: u32) -> u32 {
+    match fmt {
+        0b00 => 24,
+        0b01 => 53,
+        0b10 => 11,
+        _ => 113,
+    }
+}
+
+/// Default rounding mode of a conversion from a `bits` wide integer, `rne`
+/// when the result is always exact like GNU as does.
+fn int_to_float_rm(fmt: u32, bits: u32) -> u32 {
+    if precision(fmt) >= bits { RM_RNE } else { RM_DYN }
+}
+
+/// Default rounding mode of a float to float conversion, `rne` when widening.
+fn float_to_float_rm(dest: u32, src: u32) -> u32 {
+    if precision(dest) > precision(src) { RM_RNE } else { RM_DYN }
+}
+
+/// Checks that a shift amount fits in `bits` bits.
+fn shamt(imm: u64, bits: u32) -> Result<u64, EncodeError> {
+    cimm(imm, bits, 1, false)
+}
+
+/// Checks that a shift amount is less than the register width.
+fn xlen_shamt(imm: u64) -> Result<u64, EncodeError> {
+    shamt(imm, xlen().trailing_zeros())
+}
+
+/// Whether `imm` is a multiple of `scale` and fits in `bits` bits.
+fn fits(imm: u64, bits: u32, scale: u64, signed: bool) -> bool {
+    let in_range = if signed {
+        matches!((imm as i64) >> (bits - 1), -1 | 0)
+    } else {
+        imm >> bits == 0
+    };
+
+    in_range && imm.is_multiple_of(scale)
+}
+
+/// Checks the round number of `aes64ks1i`, 0 to 10.
+fn round_number(rnum: u64) -> Result<u64, EncodeError> {
+    if rnum > 0xA {
+        return Err(EncodeError::Immediate(rnum as i64));
+    }
+
+    Ok(rnum)
+}
+
+/// Checks that `imm` is a multiple of `scale` and fits in `bits` bits.
+fn cimm(imm: u64, bits: u32, scale: u64, signed: bool) -> Result<u64, EncodeError> {
+    if !fits(imm, bits, scale, signed) {
+        return Err(EncodeError::Immediate(imm as i64));
+    }
+
+    Ok(imm)
+}
+
+/// Instructions loading `imm` into `rd`, the sequence LLVM picks without its
+/// Zba variants.
+pub fn li_nodes(rd: u32, imm: i64) -> Vec<AstNode> {
+    let mut nodes = Vec::new();
+
+    li_sequence(rd, imm, &mut nodes);
+
+    // build the value shifted left and move it back with `srli`, filling the
+    // low bits with ones or zeros, when that is shorter
+    if nodes.len() > 2 && xlen() == 64 {
+        let zeros = (imm as u64).leading_zeros();
+        let shifted = (imm as u64) << zeros;
+
+        for fill in [shifted | ((1 << zeros) - 1), shifted] {
+            let mut shorter = Vec::new();
+
+            li_sequence(rd, fill as i64, &mut shorter);
+            shorter.push(AstNode::Srli {
+                rd,
+                rs1: rd,
+                imm: zeros as u64,
+            });
+
+            if shorter.len() < nodes.len() {
+                nodes = shorter;
+            }
+        }
+    }
+
+    nodes
+}
+
+fn li_sequence(rd: u32, imm: i64, nodes: &mut Vec<AstNode>) {
+    let lo12 = (imm << 52) >> 52;
+
+    if xlen() == 32 || imm == imm as i32 as i64 {
+        let hi20 = ((imm + 0x800) >> 12) & 0xFFFFF;
+
+        if hi20 != 0 {
+            nodes.push(AstNode::Lui {
+                rd,
+                imm: hi20 as u64,
+            });
+        }
+
+        // `addiw` wraps the carry out of `lui` to 32 bits on RV64
+        if lo12 != 0 || hi20 == 0 {
+            nodes.push(match hi20 {
+                0 => AstNode::Addi {
+                    rd,
+                    rs1: 0,
+                    imm: lo12 as u64,
+                },
+                _ if xlen() == 64 => AstNode::Addiw {
+                    rd,
+                    rs1: rd,
+                    imm: lo12 as u64,
+                },
+                _ => AstNode::Addi {
+                    rd,
+                    rs1: rd,
+                    imm: lo12 as u64,
+                },
+            });
+        }
+
+        return;
+    }
+
+    let hi52 = (imm as u64).wrapping_add(0x800) >> 12;
+    let mut shift = 12 + hi52.trailing_zeros();
+    let mut hi = (((hi52 >> (shift - 12)) << shift) as i64) >> shift;
+
+    // a `lui` can replace the low 12 zero bits of the shift
+    if shift > 12 && !(-2048..2048).contains(&hi) && (hi << 12) == ((hi << 12) as i32 as i64) {
+        shift -= 12;
+        hi <<= 12;
+    }
+
+    li_sequence(rd, hi, nodes);
+
+    nodes.push(AstNode::Slli {
+        rd,
+        rs1: rd,
+        imm: shift as u64,
+    });
+
+    if lo12 != 0 {
+        nodes.push(AstNode::Addi {
+            rd,
+            rs1: rd,
+            imm: lo12 as u64,
+        });
+    }
+}
+
+/// Sign extends the 20 bit immediate of `lui`.
+fn lui_imm(imm: u64) -> u64 {
+    (((imm << 44) as i64) >> 44) as u64
+}
+
+/// Checks that `reg` isn't one of `reserved`, which would make it another
+/// instruction or a reserved encoding.
+fn not_reg(reg: u32, reserved: &[u32], reason: &'static str) -> Result<u32, EncodeError> {
+    if reserved.contains(&reg) {
+        return Err(EncodeError::Register(reg, reason));
+    }
+
+    Ok(reg)
+}
+
+/// Checks an immediate that can't be zero.
+fn nonzero(imm: u64) -> Result<u64, EncodeError> {
+    if imm == 0 {
+        return Err(EncodeError::Immediate(0));
+    }
+
+    Ok(imm)
+}
+
+/// Checks that `reg` is the even register of a pair.
+fn even_reg(reg: u32) -> Result<u32, EncodeError> {
+    if !reg.is_multiple_of(2) {
+        return Err(EncodeError::Register(reg, "pairs start at an even register"));
+    }
+
+    Ok(reg)
+}
+
+/// Number of a register in the 3 bit fields of compressed instructions.
+fn creg(reg: u32) -> Result<u32, EncodeError> {
+    if !(8..16).contains(&reg) {
+        return Err(EncodeError::Register(reg, "compressed instructions only take x8-x15"));
     }
 
-    result
+    Ok(reg - 8)
+}
+
+/// Offset of a stack pointer relative compressed load or store.
+fn sp_offset(rs1: u32, imm: u64) -> Result<u64, EncodeError> {
+    if rs1 != 2 {
+        return Err(EncodeError::Register(rs1, "the base must be sp"));
+    }
+
+    Ok(imm)
+}
+
+/// Immediate of `c.addi4spn` and `c.addi16sp`, which must be non zero.
+fn nonzero_sp(rs1: u32, imm: u64) -> Result<u64, EncodeError> {
+    sp_offset(rs1, nonzero(imm)?)
+}
+
+/// Number of bytes `encode` will emit for `node`.
+fn node_size(node: &AstNode) -> u64 {
+    match node {
+        AstNode::Assci { seq } => seq.len() as u64,
+        AstNode::La { .. } => 8,
+        AstNode::Label { content, .. } => content.iter().map(node_size).sum(),
+        AstNode::CAddi4spn { .. }
+        | AstNode::CFld { .. }
+        | AstNode::CLw { .. }
+        | AstNode::CFlw { .. }
+        | AstNode::CLd { .. }
+        | AstNode::CFsd { .. }
+        | AstNode::CSw { .. }
+        | AstNode::CFsw { .. }
+        | AstNode::CSd { .. }
+        | AstNode::CNop { .. }
+        | AstNode::CAddi { .. }
+        | AstNode::CAddiw { .. }
+        | AstNode::CLi { .. }
+        | AstNode::CAddi16sp { .. }
+        | AstNode::CLui { .. }
+        | AstNode::CSrli { .. }
+        | AstNode::CSrai { .. }
+        | AstNode::CAndi { .. }
+        | AstNode::CSub { .. }
+        | AstNode::CXor { .. }
+        | AstNode::COr { .. }
+        | AstNode::CAnd { .. }
+        | AstNode::CSubw { .. }
+        | AstNode::CAddw { .. }
+        | AstNode::CJ { .. }
+        | AstNode::CJal { .. }
+        | AstNode::CBeqz { .. }
+        | AstNode::CBnez { .. }
+        | AstNode::CSlli { .. }
+        | AstNode::CFldsp { .. }
+        | AstNode::CLwsp { .. }
+        | AstNode::CFlwsp { .. }
+        | AstNode::CLdsp { .. }
+        | AstNode::CJr { .. }
+        | AstNode::CJalr { .. }
+        | AstNode::CMv { .. }
+        | AstNode::CAdd { .. }
+        | AstNode::CEbreak { .. }
+        | AstNode::CFsdsp { .. }
+        | AstNode::CSwsp { .. }
+        | AstNode::CFswsp { .. }
+        | AstNode::CSdsp { .. } => 2,
+        AstNode::Insn { insn } => insn.size(),
+        AstNode::OptionRvc { .. } | AstNode::Attribute { .. } => 0,
+        _ => 4,
+    }
+}
+
+/// Whether `node` is a 16 bit instruction, data of the same size doesn't count.
+fn is_compressed(node: &AstNode) -> bool {
+    !matches!(node, AstNode::Assci { .. }) && node_size(node) == 2
+}
+
+/// Alignment of each chunk of a section, code only needs 2 bytes with compressed instructions.
+fn section_align(kind: SectionKind) -> u64 {
+    if kind == SectionKind::Text && is_enabled(Extension::C) {
+        2
+    } else {
+        4
+    }
 }
 
 fn section_opts(name: &str) -> (&str, SectionKind, SymbolKind) {
@@ -202,123 +3435,135 @@ fn section_opts(name: &str) -> (&str, SectionKind, SymbolKind) {
     }
 }
 
-fn encode_label(
-    a: &mut Elf,
-    section_name: &str,
-    name: String,
-    content: Vec<AstNode>,
-    kind: SymbolKind,
-    symbol_info: &SymbolInfo,
-) {
-    let mut symbol_content = Vec::new();
-    let id = a.search_section(section_name.to_string()).id;
-    for node in content {
-        symbol_content.extend(encode(node, a, id));
+/// Attributes of the target. The arch covers every extension the file enables,
+/// `.option arch, +v` halfway through still needs a V capable core.
+fn default_attributes() -> Attributes {
+    let stack_align = match (is_embedded(), xlen()) {
+        (true, 32) => 4,
+        (true, _) => 8,
+        _ => 16,
+    };
+
+    let mut attributes = Attributes::default();
+    attributes.set(TAG_RISCV_STACK_ALIGN, AttributeValue::Number(stack_align));
+    attributes.set(TAG_RISCV_ARCH, AttributeValue::Text(used_arch_string()));
+    attributes.set(TAG_RISCV_UNALIGNED_ACCESS, AttributeValue::Number(0));
+    attributes.set(TAG_RISCV_PRIV_SPEC, AttributeValue::Number(1));
+    attributes.set(TAG_RISCV_PRIV_SPEC_MINOR, AttributeValue::Number(12));
+    attributes.set(TAG_RISCV_PRIV_SPEC_REVISION, AttributeValue::Number(0));
+
+    attributes
+}
+
+/// Applies the `.attribute` directives found in `nodes`, later ones win.
+fn collect_attributes(nodes: &[AstNode], attributes: &mut Attributes) {
+    for node in nodes {
+        match node {
+            AstNode::Attribute { tag, value } => attributes.set(*tag, value.clone()),
+            AstNode::Section { content, .. } | AstNode::Label { content, .. } => {
+                collect_attributes(content, attributes)
+            }
+            _ => {}
+        }
     }
-    a.create_symbol(
-        section_name.to_string(),
-        name,
-        kind,
-        &symbol_content,
-        4,
-        symbol_info,
-    );
 }
 
+/// Lays out and encodes `sections`, `rvc` tells whether the target starts with C.
 pub fn encode_sections<'a>(
     sections: Vec<AstNode>,
     visibility_map: HashMap<String, SymbolInfo>,
-) -> Elf<'a> {
-    let mut elf = Elf::new();
+    abi: Abi,
+    mut rvc: bool,
+) -> Result<Elf<'a>, EncodeError> {
+    let mut elf = Elf::new(if xlen() == 32 {
+        Architecture::Riscv32
+    } else {
+        Architecture::Riscv64
+    });
+    let tmp = SymbolInfo {
+        ..Default::default()
+    };
 
-    for section in sections.clone() {
+    // Lay out every label first, so branches and `la` can refer to labels
+    // defined later in the file or in another section.
+    for section in &sections {
         if let AstNode::Section { name, content } = section {
-            let (sec_name, sec_kind, sym_kind) = section_opts(&name);
-
-            let (id, section_name) = {
-                let s = elf.create_section(sec_name.to_string(), sec_kind);
-
-                (s.id, s.name.clone())
+            let (sec_name, sec_kind, sym_kind) = section_opts(name);
+            let id = match elf.sections.get(sec_name) {
+                Some(id) => *id,
+                None => elf.create_section(sec_name, sec_kind),
             };
-            let mut opcodes = Vec::new();
+
+            elf.align_values(id, section_align(sec_kind));
 
             for node in content {
                 match node {
-                    AstNode::Label { name, content } => {
-                        let tmp = SymbolInfo {
-                            ..Default::default()
-                        };
-                        let visiblity = visibility_map.get(&name).unwrap_or(&tmp);
-
-                        for c in &content {
-                            // TODO: error if symbol not exists
-                            match c {
-                                AstNode::La { rd, symbol } => {
-                                    if !elf.symbols.contains_key(symbol) {
-                                        if !search_label_encode(
-                                            symbol,
-                                            &sections,
-                                            &mut elf,
-                                            &section_name,
-                                            &visibility_map,
-                                        ) {
-                                            panic!("symbol not exist");
-                                        }
-                                    }
-
-                                    // exists
-                                }
-                                _ => {}
-                            }
-                        }
-
-                        encode_label(&mut elf, &section_name, name, content, sym_kind, visiblity);
+                    AstNode::Label { name, .. } => {
+                        let visiblity = visibility_map.get(name).unwrap_or(&tmp);
+
+                        elf.create_symbol(CreateSymbolInfo {
+                            name,
+                            size: node_size(node),
+                            section_id: id,
+                            symbol_kind: sym_kind,
+                            symbol_info: visiblity,
+                        });
                     }
-                    n => opcodes.extend(encode(n, &mut elf, id)),
+                    n => elf.reserve(id, node_size(n)),
                 }
             }
-
-            elf.write_section(id, &opcodes, 4);
         }
     }
 
-    elf
-}
+    elf.add_flags(match abi.float {
+        FloatAbi::Soft => EF_RISCV_FLOAT_ABI_SOFT,
+        FloatAbi::Single => EF_RISCV_FLOAT_ABI_SINGLE,
+        FloatAbi::Double => EF_RISCV_FLOAT_ABI_DOUBLE,
+        FloatAbi::Quad => EF_RISCV_FLOAT_ABI_QUAD,
+    });
 
-pub fn search_label_encode(
-    label: &String,
-    start_content: &Vec<AstNode>,
-    elf: &mut Elf,
-    section_name: &str,
-    visibility_map: &HashMap<String, SymbolInfo>,
-) -> bool {
-    for c in start_content {
-        match c {
-            AstNode::Section { name, content } => {
-                println!("{name}");
-                if search_label_encode(label, content, elf, name, visibility_map) {
-                    return true;
-                }
-            }
-            AstNode::Label { name, content } => {
-                if name == label {
-                    let (sec_name, sec_kind, sym_kind) = section_opts(section_name);
-                    if !elf.sections.contains_key(sec_name) {
-                        elf.create_section(sec_name.to_string(), sec_kind);
-                    }
-                    let tmp = SymbolInfo {
-                        ..Default::default()
-                    };
-                    let v = visibility_map.get(name).unwrap_or(&tmp);
+    if abi.embedded {
+        elf.add_flags(EF_RISCV_RVE);
+    }
+
+    if is_used(Extension::Ztso) {
+        elf.add_flags(EF_RISCV_TSO);
+    }
+
+    let mut attributes = default_attributes();
+    collect_attributes(&sections, &mut attributes);
+    elf.add_attributes(&attributes);
+
+    for section in sections {
+        if let AstNode::Section { name, content } = section {
+            let (sec_name, sec_kind, _) = section_opts(&name);
+            let id = elf.sections[sec_name];
+            let align = section_align(sec_kind);
+            let mut opcodes = Vec::new();
+
+            PC.store(elf.section_end(id, align), Ordering::SeqCst);
+
+            for node in content {
+                let nodes = match node {
+                    AstNode::Label { content, .. } => content,
+                    n => vec![n],
+                };
 
-                    encode_label(elf, sec_name, label.clone(), content.clone(), sym_kind, v);
-                    return true;
+                for n in nodes {
+                    rvc |= is_compressed(&n);
+                    opcodes.extend(encode(n, &mut elf, id)?);
                 }
             }
-            //AstNode::La { rd, symbol } => panic!("Double la is not allowed... for now"),
-            _ => {}
+
+            elf.write_section(id, &opcodes, align);
         }
     }
 
-    false
+    // like GNU as, a target starting with C is marked even without compressed
+    // instructions, and `.option rvc` marks the rest
+    if rvc {
+        elf.add_flags(EF_RISCV_RVC);
+    }
+
+    Ok(elf)
 }
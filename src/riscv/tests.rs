@@ -0,0 +1,547 @@
+// Encodings checked against the words llvm-mc and GNU as emit for the same source.
+
+use std::sync::{Mutex, PoisonError};
+
+use logos::Logos;
+use object::FileFlags;
+
+use super::{
+    EncodeError,
+    compress::compress_sections,
+    csr::{csr_extension, csr_xlen},
+    custom::{CustomIsaError, load_isa_ext},
+    encode_sections,
+    isa::{
+        Abi, Extension, IsaError, also_required_extension, arch_string, is_enabled, set_march,
+    },
+};
+use crate::{
+    elf::obj::Elf,
+    parser::{ast::nodes_from_tokens, token::Token},
+};
+
+/// The target, PC and symbols are global, tests touching them take turns.
+static TARGET: Mutex<()> = Mutex::new(());
+
+/// Assembles `source` in `.text` for `march` and `mabi` like `main` does.
+fn object(march: &str, mabi: Option<&str>, source: &str) -> Result<Elf<'static>, EncodeError> {
+    let _target = TARGET.lock().unwrap_or_else(PoisonError::into_inner);
+
+    set_march(march).unwrap();
+
+    let abi = match mabi {
+        Some(name) => Abi::parse(name).unwrap(),
+        None => Abi::for_target(),
+    };
+    let source = format!(".section .text\n{source}");
+    let rvc = is_enabled(Extension::C);
+    let mut lex = Token::lexer(&source);
+    let (nodes, visibility_map) = nodes_from_tokens(&mut lex, source.clone());
+    let sections = compress_sections(nodes, &visibility_map, rvc);
+
+    encode_sections(sections, visibility_map, abi, rvc)
+}
+
+/// Assembles `source` for `march` and returns `.text`.
+fn assemble(march: &str, source: &str) -> Result<Vec<u8>, EncodeError> {
+    let elf = object(march, None, source)?;
+
+    Ok(elf.elf.section(elf.sections["text"]).data().to_vec())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// `.ascii` of `len` bytes, to put labels at an exact distance.
+fn pad(len: usize) -> String {
+    format!(".ascii \"{}\"\n", "a".repeat(len))
+}
+
+#[test]
+fn branch_range_edges() {
+    let text = assemble("rv64g", &format!("t:\n{}beq a0, a1, t\n", pad(4096))).unwrap();
+    assert_eq!(hex(&text[4096..]), "6300b580");
+
+    let text = assemble("rv64g", &format!("beq a0, a1, t\n{}t:\n", pad(4090))).unwrap();
+    assert_eq!(hex(&text[..4]), "e30fb57e");
+
+    let err = assemble("rv64g", &format!("beq a0, a1, t\n{}t:\n", pad(4092))).unwrap_err();
+    assert!(matches!(err, EncodeError::OutOfRange(_, 4096)));
+
+    let err = assemble("rv64g", &format!("t:\n{}beq a0, a1, t\n", pad(4098))).unwrap_err();
+    assert!(matches!(err, EncodeError::OutOfRange(_, -4098)));
+
+    let err = assemble("rv64g", &format!("beq a0, a1, t\n{}t:\n", pad(1))).unwrap_err();
+    assert!(matches!(err, EncodeError::Misaligned(_, 5)));
+}
+
+#[test]
+fn load_store_offsets() {
+    let text = assemble("rv64g", "lw a0, -2048(a1)\nsw a0, 2047(a1)\n").unwrap();
+    assert_eq!(hex(&text), "03a50580a3afa57e");
+
+    let err = assemble("rv64g", "lw a0, 9999(a1)\n").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(9999)));
+}
+
+#[test]
+fn upper_immediates() {
+    let text = assemble("rv64g", "lui a0, 0xfffff\nauipc a0, 0xfffff\n").unwrap();
+    assert_eq!(hex(&text), "37f5ffff17f5ffff");
+
+    let err = assemble("rv64g", "lui a0, 0x100000\n").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(0x100000)));
+
+    let err = assemble("rv64g", "auipc a0, 0x100001\n").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(0x100001)));
+
+    // the low bits would fit `c.lui`, the value doesn't fit `lui`
+    let err = assemble("rv64gc", "lui a0, 0x100001\n").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(0x100001)));
+}
+
+#[test]
+fn jump_range_edges() {
+    let text = assemble("rv64g", &format!("t:\n{}jal zero, t\n", pad(1 << 20))).unwrap();
+    assert_eq!(hex(&text[1 << 20..]), "6f000080");
+
+    let text = assemble("rv64g", &format!("jal ra, t\n{}t:\n", pad((1 << 20) - 6))).unwrap();
+    assert_eq!(hex(&text[..4]), "eff0ff7f");
+
+    let err = assemble("rv64g", &format!("jal ra, t\n{}t:\n", pad((1 << 20) - 4))).unwrap_err();
+    assert!(matches!(err, EncodeError::OutOfRange(_, 1048576)));
+}
+
+#[test]
+fn alu_immediates() {
+    let text = assemble("rv64g", "addi a0, a0, -2048\naddi a0, a0, 2047\n").unwrap();
+    assert_eq!(hex(&text), "130505801305f57f");
+
+    let err = assemble("rv64g", "addi a0, a0, 2048\n").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(2048)));
+}
+
+#[test]
+fn shift_amounts() {
+    let text = assemble("rv64g", "slli a0, a1, 63\nsrai a0, a1, 63\nslliw a0, a1, 31\n").unwrap();
+    assert_eq!(hex(&text), "1395f50313d5f5431b95f501");
+    assert_eq!(hex(&assemble("rv32g", "slli a0, a1, 31\n").unwrap()), "1395f501");
+
+    let err = assemble("rv64g", "slli a0, a1, 64\n").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(64)));
+
+    let err = assemble("rv32g", "slli a0, a1, 32\n").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(32)));
+
+    let err = assemble("rv64g", "slliw a0, a1, 32\n").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(32)));
+}
+
+#[test]
+fn compresses_base_instructions() {
+    let source = "addi a0, a0, 1\nadd a0, a0, a1\nlw a0, 4(a1)\nld ra, 8(sp)\njalr zero, 0(ra)\n";
+
+    assert_eq!(hex(&assemble("rv64gc", source).unwrap()), "05052e95c841a2608280");
+    assert_eq!(hex(&assemble("rv64gc", ".option norvc\naddi a0, a0, 1\n").unwrap()), "13051500");
+    assert_eq!(hex(&assemble("rv64g", "addi a0, a0, 1\n").unwrap()), "13051500");
+}
+
+#[test]
+fn compresses_branches_in_range() {
+    let text = assemble("rv64gc", &format!("beq a0, zero, t\n{}t:\n", pad(252))).unwrap();
+    assert_eq!((text.len(), hex(&text[..2])), (254, "7dcd".to_string()));
+
+    let text = assemble("rv64gc", &format!("beq a0, zero, t\n{}t:\n", pad(254))).unwrap();
+    assert_eq!((text.len(), hex(&text[..4])), (258, "63010510".to_string()));
+
+    let text = assemble("rv64gc", &format!("t:\n{}bne a0, zero, t\n", pad(256))).unwrap();
+    assert_eq!(hex(&text[256..]), "01f1");
+
+    let text = assemble("rv64gc", &format!("t:\n{}bne a0, zero, t\n", pad(258))).unwrap();
+    assert_eq!(hex(&text[258..]), "e31f05ee");
+
+    let text = assemble("rv64gc", &format!("jal zero, t\n{}t:\n", pad(2044))).unwrap();
+    assert_eq!((text.len(), hex(&text[..2])), (2046, "fdaf".to_string()));
+
+    let text = assemble("rv64gc", &format!("jal zero, t\n{}t:\n", pad(2046))).unwrap();
+    assert_eq!((text.len(), hex(&text[..4])), (2050, "6f003000".to_string()));
+
+    let text = assemble("rv64gc", &format!("t:\n{}jal zero, t\n", pad(2048))).unwrap();
+    assert_eq!(hex(&text[2048..]), "01b0");
+
+    assert_eq!(hex(&assemble("rv32gc", "jal ra, t\nt:\n").unwrap()), "0920");
+    // other files can see `t`, the linker decides
+    assert_eq!(assemble("rv64gc", ".globl t\nbeq a0, zero, t\nt:\n").unwrap().len(), 4);
+}
+
+#[test]
+fn isa_strings() {
+    let _target = TARGET.lock().unwrap_or_else(PoisonError::into_inner);
+
+    let canonical = |march: &str| set_march(march).map(|_| arch_string());
+
+    assert_eq!(
+        canonical("rv64gc").unwrap(),
+        "rv64i2p1_m2p0_a2p1_f2p2_d2p2_c2p0_zicsr2p0_zifencei2p0"
+    );
+    assert_eq!(canonical("RV32IMAC_zba").unwrap(), "rv32i2p1_m2p0_a2p1_c2p0_zba1p0");
+    assert_eq!(canonical("rv64i2p1m2p0_zicsr2p0").unwrap(), "rv64i2p1_m2p0_zicsr2p0");
+    // D needs F and both need Zicsr
+    assert_eq!(canonical("rv64id").unwrap(), "rv64i2p1_f2p2_d2p2_zicsr2p0");
+    assert_eq!(canonical("rv32ec").unwrap(), "rv32e2p0_c2p0");
+
+    assert!(matches!(canonical("rv128i"), Err(IsaError::InvalidBase(_))));
+    assert!(matches!(canonical("rv64x"), Err(IsaError::InvalidBase(_))));
+    assert!(matches!(canonical("rv64i_zfoo"), Err(IsaError::UnknownExtension(_))));
+}
+
+#[test]
+fn insn_formats() {
+    let source = ".insn r 0x33, 0, 0, a0, a1, a2\n.insn i 0x13, 0, a0, a1, -1\nt:\n\
+                  .insn b 0x63, 0, a0, a1, t\n.insn s 0x23, 2, a0, 8(sp)\n";
+    let text = assemble("rv64g", source).unwrap();
+    assert_eq!(hex(&text), "3385c5001385f5ff6300b5002324a100");
+
+    let text = assemble("rv64gc", ".insn 0x00c58533\n.insn ci 1, 0, a0, 1\n").unwrap();
+    assert_eq!(hex(&text), "3385c5000505");
+
+    for (source, value) in [
+        (".insn r 0x33, 8, 0, a0, a1, a2", 8),
+        (".insn r 0x33, 0, 128, a0, a1, a2", 128),
+        (".insn i 0x11, 0, a0, a1, 1", 0x11),
+        (".insn r 0x133, 0, 0, a0, a1, a2", 0x133),
+        (".insn ci 3, 0, a0, 1", 3),
+    ] {
+        let err = assemble("rv64gc", source).unwrap_err();
+        assert!(matches!(err, EncodeError::Immediate(v) if v == value), "{source}");
+    }
+}
+
+#[test]
+fn rv64_word_instructions() {
+    let source = "addw a0, a1, a2\nsubw a0, a1, a2\nsllw a0, a1, a2\nsrlw a0, a1, a2\n\
+                  sraw a0, a1, a2\naddiw a0, a1, -1\nsrliw a0, a1, 31\nsraiw a0, a1, 1\n";
+    let text = assemble("rv64g", source).unwrap();
+    assert_eq!(hex(&text), "3b85c5003b85c5403b95c5003bd5c5003bd5c5401b85f5ff1bd5f5011bd51540");
+}
+
+#[test]
+fn multiply_divide() {
+    let source = "mul a0, a1, a2\nmulh a0, a1, a2\nmulhsu a0, a1, a2\nmulhu a0, a1, a2\n\
+                  div a0, a1, a2\ndivu a0, a1, a2\nrem a0, a1, a2\nremu a0, a1, a2\n\
+                  mulw a0, a1, a2\ndivw a0, a1, a2\ndivuw a0, a1, a2\nremw a0, a1, a2\n\
+                  remuw a0, a1, a2\n";
+    let text = assemble("rv64g", source).unwrap();
+    assert_eq!(
+        hex(&text),
+        concat!(
+            "3385c5023395c50233a5c50233b5c50233c5c50233d5c50233e5c50233f5c502",
+            "3b85c5023bc5c5023bd5c5023be5c5023bf5c502",
+        )
+    );
+}
+
+#[test]
+fn atomics() {
+    let source = "lr.w a0, (a1)\nsc.w a0, a2, (a1)\nlr.d.aq a0, (a1)\nsc.d.rl a0, a2, (a1)\n\
+                  amoswap.w.aqrl a0, a2, (a1)\namoadd.d a0, a2, (a1)\namoxor.w a0, a2, (a1)\n\
+                  amoand.w a0, a2, (a1)\namoor.d.aq a0, a2, (a1)\namomin.w a0, a2, (a1)\n\
+                  amomax.d a0, a2, (a1)\namominu.w a0, a2, (a1)\namomaxu.d.rl a0, a2, (a1)\n";
+    let text = assemble("rv64g", source).unwrap();
+    assert_eq!(
+        hex(&text),
+        concat!(
+            "2fa505102fa5c5182fb505142fb5c51a2fa5c50e2fb5c5002fa5c5202fa5c560",
+            "2fb5c5442fa5c5802fb5c5a02fa5c5c02fb5c5e2",
+        )
+    );
+}
+
+#[test]
+fn float_double() {
+    let source = "flw fa0, 8(a0)\nfsw fa1, -4(sp)\nfld fa0, 16(a1)\nfsd fa1, 0(a1)\n\
+                  fadd.s fa0, fa1, fa2\nfadd.d fa0, fa1, fa2, rtz\nfmadd.d fa0, fa1, fa2, fa3\n\
+                  fsqrt.s fa0, fa1\nfsgnjn.d fa0, fa1, fa2\nfmin.s fa0, fa1, fa2\n\
+                  feq.d a0, fa1, fa2\nfclass.s a0, fa0\nfcvt.w.s a0, fa0, rtz\n\
+                  fcvt.d.s fa0, fa1\nfcvt.s.d fa0, fa1\nfcvt.l.d a0, fa0\nfcvt.d.lu fa0, a0\n\
+                  fmv.x.w a0, fa0\nfmv.d.x fa0, a0\n";
+    let text = assemble("rv64g", source).unwrap();
+    assert_eq!(
+        hex(&text),
+        concat!(
+            "07258500272eb1fe07b5050127b0b50053f5c5005395c50243f5c56a53f50558",
+            "5395c5225385c52853a5c5a2531505e0531505c05385054253f51540537525c2",
+            "537535d2530505e0530505f2",
+        )
+    );
+}
+
+#[test]
+fn compressed_register_operands() {
+    let source = "c.add a0, a1\nc.mv a0, a1\nc.lui a0, 1\nc.lui a0, 0xfffff\nc.jr a0\nc.jalr a0\n";
+    assert_eq!(hex(&assemble("rv64gc", source).unwrap()), "2e952e8505657d7502850295");
+
+    // each of these is another instruction or a reserved encoding
+    for source in ["c.add a0, zero", "c.mv a0, zero", "c.jr zero", "c.jalr zero"] {
+        let err = assemble("rv64gc", source).unwrap_err();
+        assert!(matches!(err, EncodeError::Register(0, _)), "{source}");
+    }
+
+    let err = assemble("rv64gc", "c.lui sp, 1").unwrap_err();
+    assert!(matches!(err, EncodeError::Register(2, _)));
+
+    let err = assemble("rv64gc", "c.lui a0, 0").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(0)));
+}
+
+#[test]
+fn compressed_register_limits() {
+    let source = "c.lw a0, 4(a1)\nc.addi4spn a0, sp, 8\nc.lwsp a0, 4(sp)\nc.addi16sp sp, 16\n";
+    assert_eq!(hex(&assemble("rv64gc", source).unwrap()), "c841280012454161");
+
+    let err = assemble("rv64gc", "c.lw a0, 0(t0)").unwrap_err();
+    assert!(matches!(err, EncodeError::Register(5, _)));
+
+    let err = assemble("rv64gc", "c.lwsp a0, 4(a1)").unwrap_err();
+    assert!(matches!(err, EncodeError::Register(11, _)));
+
+    let err = assemble("rv64gc", "c.addi4spn a0, sp, 0").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(0)));
+}
+
+#[test]
+fn rv32fc_loads_and_stores() {
+    let source = "c.flw fa0, 4(a1)\nc.fsw fa1, 124(a0)\nc.flwsp fa0, 252(sp)\nc.fswsp fa1, 8(sp)\n\
+                  flw fa0, 4(a1)\nfsw fa1, 8(sp)\nflw fa0, 128(a1)\n";
+    let text = assemble("rv32gc", source).unwrap();
+    assert_eq!(hex(&text), "c8616cfd7e752ee4c8612ee407a50508");
+
+    // RV64 has no `c.flw`, the word goes through uncompressed
+    assert_eq!(hex(&assemble("rv64gc", "flw fa0, 4(a1)\n").unwrap()), "07a54500");
+}
+
+#[test]
+fn csr_operands() {
+    let source = "csrr a0, cycle\ncsrw mstatus, a1\ncsrrwi a0, fcsr, 5\ncsrrc a0, 0x7c0, a1\n\
+                  rdinstret a2\nfrcsr a3\ncsrr a0, instreth\ncsrr a0, mcycleh\n\
+                  csrr a0, hpmcounter3h\n";
+    let text = assemble("rv32if", source).unwrap();
+    assert_eq!(
+        hex(&text),
+        concat!(
+            "732500c07390053073d5320073b5057c732620c0f3263000",
+            "732520c8732500b8732530c8"
+        )
+    );
+
+    // the upper halves only exist on RV32
+    for name in ["cycleh", "timeh", "instreth", "mstatush", "hpmcounter31h"] {
+        assert_eq!(csr_xlen(name), Some(32), "{name}");
+    }
+    for name in ["cycle", "mstatus", "hpmcounter31"] {
+        assert_eq!(csr_xlen(name), None, "{name}");
+    }
+}
+
+#[test]
+fn vector_register_groups() {
+    let source = "vmv1r.v v1, v3\nvmv2r.v v2, v4\nvmv8r.v v8, v16\nvl2re32.v v2, (a0)\n\
+                  vl8re8.v v24, (a1)\nvl1re64.v v3, (a0)\nvs4r.v v4, (a0)\nvs1r.v v5, (a2)\n\
+                  vle32.v v1, (a0)\nvadd.vv v1, v2, v3\n";
+    let text = assemble("rv64gcv", source).unwrap();
+    assert_eq!(
+        hex(&text),
+        concat!(
+            "d730309e57b1409e57b4039f07618522078c85e28771850227028562",
+            "a702860287600502d7802102"
+        )
+    );
+
+    for (source, reg) in [
+        ("vmv2r.v v1, v2", 1),
+        ("vmv4r.v v4, v6", 6),
+        ("vl2re32.v v1, (a0)", 1),
+        ("vs4r.v v2, (a0)", 2),
+    ] {
+        let err = assemble("rv64gcv", source).unwrap_err();
+        assert!(matches!(err, EncodeError::Register(r, _) if r == reg), "{source}");
+    }
+}
+
+#[test]
+fn scalar_crypto() {
+    let source = "aes64ks1i a0, a1, 10\naes64ks1i a0, a1, 0\naes64ks2 a0, a1, a2\n\
+                  sha256sig0 a0, a1\nsha512sum1 a0, a1\nsm3p0 a0, a1\nsm4ed a0, a1, a2, 3\n";
+    let text = assemble("rv64i_zkn_zks", source).unwrap();
+    assert_eq!(hex(&text), "1395a531139505313385c57e1395251013955510139585103385c5f0");
+
+    let source = "aes32esi a0, a1, a2, 3\naes32dsmi a0, a1, a2, 1\n";
+    assert_eq!(hex(&assemble("rv32i_zkn", source).unwrap()), "3385c5e23385c56e");
+
+    let err = assemble("rv64i_zkn", "aes64ks1i a0, a1, 11").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(11)));
+
+    let err = assemble("rv32i_zkn", "aes32esi a0, a1, a2, 4").unwrap_err();
+    assert!(matches!(err, EncodeError::Immediate(4)));
+}
+
+#[test]
+fn register_pairs_and_rounding() {
+    let source = "amocas.d a0, a2, (a1)\namocas.d.aqrl a2, a4, (a0)\n";
+    assert_eq!(hex(&assemble("rv32ia_zacas", source).unwrap()), "2fb5c5282f36e52e");
+
+    // RV64 has no pairs for amocas.d, only amocas.q
+    let source = "amocas.q a2, a4, (a0)\namocas.d a1, a3, (a0)\nfcvtmod.w.d a0, fa0, rtz\n";
+    let text = assemble("rv64iad_zacas_zfa", source).unwrap();
+    assert_eq!(hex(&text), "2f46e528af35d528531585c2");
+
+    for (march, source, reg) in [
+        ("rv64ia_zacas", "amocas.q a1, a2, (a0)", 11),
+        ("rv64ia_zacas", "amocas.q a2, a3, (a0)", 13),
+        ("rv32ia_zacas", "amocas.d a1, a2, (a0)", 11),
+        ("rv32ia_zacas", "amocas.d a0, a3, (a1)", 13),
+    ] {
+        let err = assemble(march, source).unwrap_err();
+        assert!(matches!(err, EncodeError::Register(r, _) if r == reg), "{source}");
+    }
+
+    let err = assemble("rv64iad_zfa", "fcvtmod.w.d a0, fa0, rne").unwrap_err();
+    assert!(matches!(err, EncodeError::RoundingMode(_)));
+}
+
+#[test]
+fn half_conversions() {
+    let source = "fcvt.d.h fa0, fa1\nfcvt.h.d fa0, fa1\nfcvt.s.h fa0, fa1\nfcvt.h.s fa0, fa1, rtz\n\
+                  fcvt.q.h fa0, fa1\nfcvt.h.q fa0, fa1\n";
+    let text = assemble("rv64ifdq_zfhmin", source).unwrap();
+    assert_eq!(hex(&text), "5385254253f5154453852540539505445385254653f53544");
+
+    // Zfhmin only brings the half side, D or Q is needed for the other one
+    let conversion = |dest, src| also_required_extension(&Token::FcvtFF((dest, src)));
+    assert_eq!(conversion(0b01, 0b10), Some(Extension::D));
+    assert_eq!(conversion(0b10, 0b01), Some(Extension::D));
+    assert_eq!(conversion(0b11, 0b10), Some(Extension::Q));
+    assert_eq!(conversion(0b10, 0b11), Some(Extension::Q));
+    assert_eq!(conversion(0b00, 0b10), None);
+    assert_eq!(conversion(0b01, 0b00), None);
+}
+
+#[test]
+fn hypervisor() {
+    let source = "csrr a0, hstatus\ncsrw hgatp, a1\ncsrr a0, mtinst\ncsrr a0, mtval2\n\
+                  csrr a0, htimedeltah\ncsrr a0, vstimecmph\nhfence.gvma zero, zero\n\
+                  hlv.w a0, (a1)\n";
+    let text = assemble("rv32ih", source).unwrap();
+    assert_eq!(hex(&text), "73250060739005687325a0347325b034732550617325d0257300006273c50568");
+
+    for name in ["hstatus", "mtinst", "mtval2", "htimedeltah", "vstimecmph"] {
+        assert_eq!(csr_extension(name), Some(Extension::H), "{name}");
+    }
+    assert_eq!(csr_extension("mtval"), None);
+
+    for name in ["hedelegh", "htimedeltah", "henvcfgh", "vstimecmph"] {
+        assert_eq!(csr_xlen(name), Some(32), "{name}");
+    }
+    assert_eq!(csr_xlen("htimedelta"), None);
+}
+
+#[test]
+fn riscv_attributes() {
+    let has_attributes = |elf: Elf<'_>, expected: &str| {
+        let bytes = elf.elf.write().unwrap();
+
+        hex(&bytes).contains(expected)
+    };
+
+    // the arch of the target and the defaults GNU as writes
+    let elf = object("rv64gc", None, "add a0, a1, a2\n").unwrap();
+    let expected = concat!(
+        "4151000000726973637600014700000004100572763634693270315f6d3270305f61",
+        "3270315f663270325f643270325f633270305f7a696373723270305f7a6966656e63",
+        "656932703000060008010a0c0c00"
+    );
+    assert!(has_attributes(elf, expected));
+
+    // `.attribute` overrides them
+    let source = ".attribute arch, \"rv64i2p1_zicsr2p0\"\n.attribute stack_align, 8\n";
+    let elf = object("rv64gc", None, source).unwrap();
+    let expected = concat!(
+        "412c000000726973637600012200000004080572763634693270315f7a696373723270",
+        "3000060008010a0c0c00"
+    );
+    assert!(has_attributes(elf, expected));
+}
+
+#[test]
+fn header_flags() {
+    let e_flags = |march, mabi, source| match object(march, mabi, source).unwrap().elf.flags {
+        FileFlags::Elf { e_flags, .. } => e_flags,
+        _ => unreachable!(),
+    };
+
+    // RVC 0x1, float ABI 0x2 to 0x6, RVE 0x8 and TSO 0x10
+    assert_eq!(e_flags("rv64gc", None, "add a0, a1, a2\n"), 0x5);
+    assert_eq!(e_flags("rv64gc", Some("lp64"), "add a0, a1, a2\n"), 0x1);
+    assert_eq!(e_flags("rv64gc", Some("lp64f"), "add a0, a1, a2\n"), 0x3);
+    assert_eq!(e_flags("rv64g", None, "add a0, a1, a2\n"), 0x4);
+    assert_eq!(e_flags("rv64g", None, ".option rvc\nc.addi a0, 1\n"), 0x5);
+    assert_eq!(e_flags("rv32imac_ztso", None, "add a0, a1, a2\n"), 0x11);
+    assert_eq!(e_flags("rv32ec", None, "add a0, a1, a2\n"), 0x9);
+}
+
+#[test]
+fn isa_ext_spec() {
+    // the instructions can be loaded once, every other spec is rejected before that
+    let dir = std::env::temp_dir().join(format!("jusm-isa-ext-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let spec_file = |name: &str, spec: &str| {
+        let path = dir.join(name);
+        std::fs::write(&path, spec).unwrap();
+
+        path.to_str().unwrap().to_string()
+    };
+    let instruction = |mnemonic: &str, format: &str, opcode: &str, fields: &str, operands: &str| {
+        format!(
+            "[[instruction]]\nmnemonic = \"{mnemonic}\"\nformat = \"{format}\"\n\
+             opcode = {opcode}\nfields = {{ {fields} }}\noperands = [{operands}]\n"
+        )
+    };
+    let r = r#""rd", "rs1", "rs2""#;
+
+    let spec = instruction("add", "r", "11", "funct3 = 0, funct7 = 1", r);
+    let err = load_isa_ext(&spec_file("builtin.toml", &spec)).unwrap_err();
+    assert!(matches!(err, CustomIsaError::Mnemonic(_)));
+
+    let spec = instruction("cx.c", "ci", "11", "funct3 = 0", r#""rd", "imm""#);
+    let err = load_isa_ext(&spec_file("opcode.toml", &spec)).unwrap_err();
+    assert!(matches!(err, CustomIsaError::InvalidOpcode(_, 11, _)));
+
+    let spec = instruction("cx.f", "r", "11", "funct3 = 8, funct7 = 1", r);
+    let err = load_isa_ext(&spec_file("field.toml", &spec)).unwrap_err();
+    assert!(matches!(err, CustomIsaError::InvalidField(_, _, 8)));
+
+    let spec = instruction("cx.m", "r", "11", "funct3 = 0, funct7 = 1", r#""rd", "rs1""#);
+    let err = load_isa_ext(&spec_file("missing.toml", &spec)).unwrap_err();
+    assert!(matches!(err, CustomIsaError::MissingField(_, _)));
+
+    let spec = r#"{ "instruction": [
+        { "mnemonic": "cx.d", "format": "u", "opcode": 11, "operands": ["rd", "imm"] },
+        { "mnemonic": "cx.d", "format": "u", "opcode": 43, "operands": ["rd", "imm"] }
+    ] }"#;
+    let err = load_isa_ext(&spec_file("duplicate.json", spec)).unwrap_err();
+    assert!(matches!(err, CustomIsaError::Duplicate(_)));
+
+    let spec = [
+        instruction("cx.mac", "r", r#""CUSTOM_0""#, "funct3 = 0, funct7 = 1", r),
+        instruction("cx.addi", "i", "0x2b", "funct3 = 1", r#""rd", "rs1", "imm""#),
+        instruction("cx.ld", "i", r#""CUSTOM_2""#, "funct3 = 3", r#""rd", "imm", "paren_rs1""#),
+    ];
+    load_isa_ext(&spec_file("spec.toml", &spec.join("\n"))).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    // the same words as `.insn r 0x0b, 0, 1, a0, a1, a2` and so on
+    let source = "cx.mac a0, a1, a2\ncx.addi a0, a1, -1\ncx.ld a0, 8(a1)\n";
+    assert_eq!(hex(&assemble("rv64g", source).unwrap()), "0b85c5022b95f5ff5bb58500");
+}
@@ -0,0 +1,566 @@
+// Vector extension (RVV 1.0), instructions are looked up by mnemonic since
+// every operation comes in several `.vv`/`.vx`/`.vi`/`.vf` forms.
+
+use super::{EncodeError, cimm};
+
+const OPIVV: u32 = 0b000;
+const OPFVV: u32 = 0b001;
+const OPMVV: u32 = 0b010;
+const OPIVI: u32 = 0b011;
+const OPIVX: u32 = 0b100;
+const OPFVF: u32 = 0b101;
+const OPMVX: u32 = 0b110;
+
+const OP_V: u32 = 0b1010111;
+const LOAD_FP: u32 = 0b0000111;
+const STORE_FP: u32 = 0b0100111;
+
+/// Kind of a register or immediate operand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Operand {
+    V,
+    X,
+    F,
+    Simm,
+    Uimm,
+}
+
+/// Operands written in the source and where they are encoded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum VectorForm {
+    /// `vd, vs2, vs1`, with the operand in place of `vs1`
+    Binary(Operand),
+    /// `vd, vs2, vs1, v0`
+    Carry(Operand),
+    /// `vd, vs1, vs2` of the multiply adds
+    Macc(Operand),
+    /// `vd, vs2`
+    Unary,
+    /// `rd, vs2` with a scalar destination
+    Scalar(Operand),
+    /// `vd, vs1` with `vs2` unused
+    Move(Operand),
+    /// `vd`
+    Dest,
+    /// `vd, vs`, reading `vs` as both sources
+    Dup,
+    /// `vd`, used as destination and both sources
+    Same,
+    /// `vd, (rs1)`
+    Unit,
+    /// `vd, (rs1), rs2`
+    Strided,
+    /// `vd, (rs1), vs2`
+    Indexed,
+}
+
+impl VectorForm {
+    /// Kind of the operand encoded in the `vs1` field.
+    fn source(self) -> Option<Operand> {
+        match self {
+            VectorForm::Binary(op)
+            | VectorForm::Carry(op)
+            | VectorForm::Macc(op)
+            | VectorForm::Move(op) => Some(op),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct VectorIns {
+    /// `funct6`, or `nf`, `mew` and `mop` of loads and stores
+    pub funct6: u32,
+    /// `funct3`, or the element width of loads and stores
+    pub funct3: u32,
+    pub opcode: u32,
+    pub form: VectorForm,
+    /// Fixed value of the field the form leaves unused, `vs1` or `vs2`
+    pub aux: u32,
+    /// Whether the instruction takes the optional `v0.t` operand
+    pub masked: bool,
+}
+
+const fn ins(funct6: u32, funct3: u32, form: VectorForm) -> VectorIns {
+    VectorIns {
+        funct6,
+        funct3,
+        opcode: OP_V,
+        form,
+        aux: 0,
+        masked: true,
+    }
+}
+
+impl VectorIns {
+    const fn aux(mut self, aux: u32) -> Self {
+        self.aux = aux;
+        self
+    }
+
+    const fn unmasked(mut self) -> Self {
+        self.masked = false;
+        self
+    }
+
+    /// Size of the register groups of `vmv2r.v`, `vl2re32.v` and `vs2r.v`.
+    fn whole_registers(self) -> Option<u32> {
+        match (self.opcode, self.form) {
+            (OP_V, VectorForm::Unary) if self.funct6 == 0b100111 && self.funct3 == OPIVI => {
+                Some(self.aux + 1)
+            }
+            (LOAD_FP | STORE_FP, VectorForm::Unit)
+                if self.funct6 & 0b111 == 0 && self.aux == 0b01000 =>
+            {
+                Some((self.funct6 >> 3) + 1)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// How the `.vi` form of a family reads its immediate and orders its operands.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Signed,
+    /// `.vi` takes an unsigned immediate, shifts and slides
+    Unsigned,
+    /// Operands are `vd, vs1, vs2`
+    Macc,
+    /// Forms without a carry in don't take a mask, `vmadc.vv`
+    Carry,
+}
+
+use Kind::{Carry, Macc, Signed, Unsigned};
+
+/// Integer families, name, `funct6`, accepted suffixes.
+const INTEGER: &[(&str, u32, Kind, &[&str])] = &[
+    ("vadd", 0b000000, Signed, &["vv", "vx", "vi"]),
+    ("vsub", 0b000010, Signed, &["vv", "vx"]),
+    ("vrsub", 0b000011, Signed, &["vx", "vi"]),
+    ("vminu", 0b000100, Signed, &["vv", "vx"]),
+    ("vmin", 0b000101, Signed, &["vv", "vx"]),
+    ("vmaxu", 0b000110, Signed, &["vv", "vx"]),
+    ("vmax", 0b000111, Signed, &["vv", "vx"]),
+    ("vand", 0b001001, Signed, &["vv", "vx", "vi"]),
+    ("vor", 0b001010, Signed, &["vv", "vx", "vi"]),
+    ("vxor", 0b001011, Signed, &["vv", "vx", "vi"]),
+    ("vrgather", 0b001100, Unsigned, &["vv", "vx", "vi"]),
+    ("vrgatherei16", 0b001110, Signed, &["vv"]),
+    ("vslideup", 0b001110, Unsigned, &["vx", "vi"]),
+    ("vslidedown", 0b001111, Unsigned, &["vx", "vi"]),
+    ("vadc", 0b010000, Signed, &["vvm", "vxm", "vim"]),
+    ("vmadc", 0b010001, Carry, &["vvm", "vxm", "vim", "vv", "vx", "vi"]),
+    ("vsbc", 0b010010, Signed, &["vvm", "vxm"]),
+    ("vmsbc", 0b010011, Carry, &["vvm", "vxm", "vv", "vx"]),
+    ("vmerge", 0b010111, Signed, &["vvm", "vxm", "vim"]),
+    ("vmseq", 0b011000, Signed, &["vv", "vx", "vi"]),
+    ("vmsne", 0b011001, Signed, &["vv", "vx", "vi"]),
+    ("vmsltu", 0b011010, Signed, &["vv", "vx"]),
+    ("vmslt", 0b011011, Signed, &["vv", "vx"]),
+    ("vmsleu", 0b011100, Signed, &["vv", "vx", "vi"]),
+    ("vmsle", 0b011101, Signed, &["vv", "vx", "vi"]),
+    ("vmsgtu", 0b011110, Signed, &["vx", "vi"]),
+    ("vmsgt", 0b011111, Signed, &["vx", "vi"]),
+    ("vsaddu", 0b100000, Signed, &["vv", "vx", "vi"]),
+    ("vsadd", 0b100001, Signed, &["vv", "vx", "vi"]),
+    ("vssubu", 0b100010, Signed, &["vv", "vx"]),
+    ("vssub", 0b100011, Signed, &["vv", "vx"]),
+    ("vsll", 0b100101, Unsigned, &["vv", "vx", "vi"]),
+    ("vsmul", 0b100111, Signed, &["vv", "vx"]),
+    ("vsrl", 0b101000, Unsigned, &["vv", "vx", "vi"]),
+    ("vsra", 0b101001, Unsigned, &["vv", "vx", "vi"]),
+    ("vssrl", 0b101010, Unsigned, &["vv", "vx", "vi"]),
+    ("vssra", 0b101011, Unsigned, &["vv", "vx", "vi"]),
+    ("vnsrl", 0b101100, Unsigned, &["wv", "wx", "wi"]),
+    ("vnsra", 0b101101, Unsigned, &["wv", "wx", "wi"]),
+    ("vnclipu", 0b101110, Unsigned, &["wv", "wx", "wi"]),
+    ("vnclip", 0b101111, Unsigned, &["wv", "wx", "wi"]),
+    ("vwredsumu", 0b110000, Signed, &["vs"]),
+    ("vwredsum", 0b110001, Signed, &["vs"]),
+];
+
+/// Mask, multiply and divide families.
+const MULTIPLY: &[(&str, u32, Kind, &[&str])] = &[
+    ("vredsum", 0b000000, Signed, &["vs"]),
+    ("vredand", 0b000001, Signed, &["vs"]),
+    ("vredor", 0b000010, Signed, &["vs"]),
+    ("vredxor", 0b000011, Signed, &["vs"]),
+    ("vredminu", 0b000100, Signed, &["vs"]),
+    ("vredmin", 0b000101, Signed, &["vs"]),
+    ("vredmaxu", 0b000110, Signed, &["vs"]),
+    ("vredmax", 0b000111, Signed, &["vs"]),
+    ("vaaddu", 0b001000, Signed, &["vv", "vx"]),
+    ("vaadd", 0b001001, Signed, &["vv", "vx"]),
+    ("vasubu", 0b001010, Signed, &["vv", "vx"]),
+    ("vasub", 0b001011, Signed, &["vv", "vx"]),
+    ("vslide1up", 0b001110, Signed, &["vx"]),
+    ("vslide1down", 0b001111, Signed, &["vx"]),
+    ("vcompress", 0b010111, Signed, &["vm"]),
+    ("vmandn", 0b011000, Signed, &["mm"]),
+    ("vmand", 0b011001, Signed, &["mm"]),
+    ("vmor", 0b011010, Signed, &["mm"]),
+    ("vmxor", 0b011011, Signed, &["mm"]),
+    ("vmorn", 0b011100, Signed, &["mm"]),
+    ("vmnand", 0b011101, Signed, &["mm"]),
+    ("vmnor", 0b011110, Signed, &["mm"]),
+    ("vmxnor", 0b011111, Signed, &["mm"]),
+    ("vdivu", 0b100000, Signed, &["vv", "vx"]),
+    ("vdiv", 0b100001, Signed, &["vv", "vx"]),
+    ("vremu", 0b100010, Signed, &["vv", "vx"]),
+    ("vrem", 0b100011, Signed, &["vv", "vx"]),
+    ("vmulhu", 0b100100, Signed, &["vv", "vx"]),
+    ("vmul", 0b100101, Signed, &["vv", "vx"]),
+    ("vmulhsu", 0b100110, Signed, &["vv", "vx"]),
+    ("vmulh", 0b100111, Signed, &["vv", "vx"]),
+    ("vmadd", 0b101001, Macc, &["vv", "vx"]),
+    ("vnmsub", 0b101011, Macc, &["vv", "vx"]),
+    ("vmacc", 0b101101, Macc, &["vv", "vx"]),
+    ("vnmsac", 0b101111, Macc, &["vv", "vx"]),
+    ("vwaddu", 0b110000, Signed, &["vv", "vx"]),
+    ("vwadd", 0b110001, Signed, &["vv", "vx"]),
+    ("vwsubu", 0b110010, Signed, &["vv", "vx"]),
+    ("vwsub", 0b110011, Signed, &["vv", "vx"]),
+    ("vwaddu", 0b110100, Signed, &["wv", "wx"]),
+    ("vwadd", 0b110101, Signed, &["wv", "wx"]),
+    ("vwsubu", 0b110110, Signed, &["wv", "wx"]),
+    ("vwsub", 0b110111, Signed, &["wv", "wx"]),
+    ("vwmulu", 0b111000, Signed, &["vv", "vx"]),
+    ("vwmulsu", 0b111010, Signed, &["vv", "vx"]),
+    ("vwmul", 0b111011, Signed, &["vv", "vx"]),
+    ("vwmaccu", 0b111100, Macc, &["vv", "vx"]),
+    ("vwmacc", 0b111101, Macc, &["vv", "vx"]),
+    ("vwmaccus", 0b111110, Macc, &["vx"]),
+    ("vwmaccsu", 0b111111, Macc, &["vv", "vx"]),
+];
+
+/// Floating point families.
+const FLOAT: &[(&str, u32, Kind, &[&str])] = &[
+    ("vfadd", 0b000000, Signed, &["vv", "vf"]),
+    ("vfredusum", 0b000001, Signed, &["vs"]),
+    ("vfsub", 0b000010, Signed, &["vv", "vf"]),
+    ("vfredosum", 0b000011, Signed, &["vs"]),
+    ("vfmin", 0b000100, Signed, &["vv", "vf"]),
+    ("vfredmin", 0b000101, Signed, &["vs"]),
+    ("vfmax", 0b000110, Signed, &["vv", "vf"]),
+    ("vfredmax", 0b000111, Signed, &["vs"]),
+    ("vfsgnj", 0b001000, Signed, &["vv", "vf"]),
+    ("vfsgnjn", 0b001001, Signed, &["vv", "vf"]),
+    ("vfsgnjx", 0b001010, Signed, &["vv", "vf"]),
+    ("vfslide1up", 0b001110, Signed, &["vf"]),
+    ("vfslide1down", 0b001111, Signed, &["vf"]),
+    ("vfmerge", 0b010111, Signed, &["vfm"]),
+    ("vmfeq", 0b011000, Signed, &["vv", "vf"]),
+    ("vmfle", 0b011001, Signed, &["vv", "vf"]),
+    ("vmflt", 0b011011, Signed, &["vv", "vf"]),
+    ("vmfne", 0b011100, Signed, &["vv", "vf"]),
+    ("vmfgt", 0b011101, Signed, &["vf"]),
+    ("vmfge", 0b011111, Signed, &["vf"]),
+    ("vfdiv", 0b100000, Signed, &["vv", "vf"]),
+    ("vfrdiv", 0b100001, Signed, &["vf"]),
+    ("vfmul", 0b100100, Signed, &["vv", "vf"]),
+    ("vfrsub", 0b100111, Signed, &["vf"]),
+    ("vfmadd", 0b101000, Macc, &["vv", "vf"]),
+    ("vfnmadd", 0b101001, Macc, &["vv", "vf"]),
+    ("vfmsub", 0b101010, Macc, &["vv", "vf"]),
+    ("vfnmsub", 0b101011, Macc, &["vv", "vf"]),
+    ("vfmacc", 0b101100, Macc, &["vv", "vf"]),
+    ("vfnmacc", 0b101101, Macc, &["vv", "vf"]),
+    ("vfmsac", 0b101110, Macc, &["vv", "vf"]),
+    ("vfnmsac", 0b101111, Macc, &["vv", "vf"]),
+    ("vfwadd", 0b110000, Signed, &["vv", "vf"]),
+    ("vfwredusum", 0b110001, Signed, &["vs"]),
+    ("vfwsub", 0b110010, Signed, &["vv", "vf"]),
+    ("vfwredosum", 0b110011, Signed, &["vs"]),
+    ("vfwadd", 0b110100, Signed, &["wv", "wf"]),
+    ("vfwsub", 0b110110, Signed, &["wv", "wf"]),
+    ("vfwmul", 0b111000, Signed, &["vv", "vf"]),
+    ("vfwmacc", 0b111100, Macc, &["vv", "vf"]),
+    ("vfwnmacc", 0b111101, Macc, &["vv", "vf"]),
+    ("vfwmsac", 0b111110, Macc, &["vv", "vf"]),
+    ("vfwnmsac", 0b111111, Macc, &["vv", "vf"]),
+];
+
+use Operand::{F, Uimm, V, X};
+use VectorForm::{Dest, Dup, Move, Same, Scalar, Unary};
+
+/// Instructions with a fixed `vs1` or `vs2` field, and pseudo instructions.
+const FIXED: &[(&str, VectorIns)] = &[
+    ("vmv.x.s", ins(0b010000, OPMVV, Scalar(X)).unmasked()),
+    ("vcpop.m", ins(0b010000, OPMVV, Scalar(X)).aux(0b10000)),
+    ("vfirst.m", ins(0b010000, OPMVV, Scalar(X)).aux(0b10001)),
+    ("vmv.s.x", ins(0b010000, OPMVX, Move(X)).unmasked()),
+    ("vzext.vf8", ins(0b010010, OPMVV, Unary).aux(0b00010)),
+    ("vsext.vf8", ins(0b010010, OPMVV, Unary).aux(0b00011)),
+    ("vzext.vf4", ins(0b010010, OPMVV, Unary).aux(0b00100)),
+    ("vsext.vf4", ins(0b010010, OPMVV, Unary).aux(0b00101)),
+    ("vzext.vf2", ins(0b010010, OPMVV, Unary).aux(0b00110)),
+    ("vsext.vf2", ins(0b010010, OPMVV, Unary).aux(0b00111)),
+    ("vmsbf.m", ins(0b010100, OPMVV, Unary).aux(0b00001)),
+    ("vmsof.m", ins(0b010100, OPMVV, Unary).aux(0b00010)),
+    ("vmsif.m", ins(0b010100, OPMVV, Unary).aux(0b00011)),
+    ("viota.m", ins(0b010100, OPMVV, Unary).aux(0b10000)),
+    ("vid.v", ins(0b010100, OPMVV, Dest).aux(0b10001)),
+    ("vfmv.f.s", ins(0b010000, OPFVV, Scalar(F)).unmasked()),
+    ("vfmv.s.f", ins(0b010000, OPFVF, Move(F)).unmasked()),
+    ("vfcvt.xu.f.v", ins(0b010010, OPFVV, Unary).aux(0b00000)),
+    ("vfcvt.x.f.v", ins(0b010010, OPFVV, Unary).aux(0b00001)),
+    ("vfcvt.f.xu.v", ins(0b010010, OPFVV, Unary).aux(0b00010)),
+    ("vfcvt.f.x.v", ins(0b010010, OPFVV, Unary).aux(0b00011)),
+    ("vfcvt.rtz.xu.f.v", ins(0b010010, OPFVV, Unary).aux(0b00110)),
+    ("vfcvt.rtz.x.f.v", ins(0b010010, OPFVV, Unary).aux(0b00111)),
+    ("vfwcvt.xu.f.v", ins(0b010010, OPFVV, Unary).aux(0b01000)),
+    ("vfwcvt.x.f.v", ins(0b010010, OPFVV, Unary).aux(0b01001)),
+    ("vfwcvt.f.xu.v", ins(0b010010, OPFVV, Unary).aux(0b01010)),
+    ("vfwcvt.f.x.v", ins(0b010010, OPFVV, Unary).aux(0b01011)),
+    ("vfwcvt.f.f.v", ins(0b010010, OPFVV, Unary).aux(0b01100)),
+    ("vfwcvt.rtz.xu.f.v", ins(0b010010, OPFVV, Unary).aux(0b01110)),
+    ("vfwcvt.rtz.x.f.v", ins(0b010010, OPFVV, Unary).aux(0b01111)),
+    ("vfncvt.xu.f.w", ins(0b010010, OPFVV, Unary).aux(0b10000)),
+    ("vfncvt.x.f.w", ins(0b010010, OPFVV, Unary).aux(0b10001)),
+    ("vfncvt.f.xu.w", ins(0b010010, OPFVV, Unary).aux(0b10010)),
+    ("vfncvt.f.x.w", ins(0b010010, OPFVV, Unary).aux(0b10011)),
+    ("vfncvt.f.f.w", ins(0b010010, OPFVV, Unary).aux(0b10100)),
+    ("vfncvt.rod.f.f.w", ins(0b010010, OPFVV, Unary).aux(0b10101)),
+    ("vfncvt.rtz.xu.f.w", ins(0b010010, OPFVV, Unary).aux(0b10110)),
+    ("vfncvt.rtz.x.f.w", ins(0b010010, OPFVV, Unary).aux(0b10111)),
+    ("vfsqrt.v", ins(0b010011, OPFVV, Unary).aux(0b00000)),
+    ("vfrsqrt7.v", ins(0b010011, OPFVV, Unary).aux(0b00100)),
+    ("vfrec7.v", ins(0b010011, OPFVV, Unary).aux(0b00101)),
+    ("vfclass.v", ins(0b010011, OPFVV, Unary).aux(0b10000)),
+    ("vmv.v.v", ins(0b010111, OPIVV, Move(V)).unmasked()),
+    ("vmv.v.x", ins(0b010111, OPIVX, Move(X)).unmasked()),
+    ("vmv.v.i", ins(0b010111, OPIVI, Move(Operand::Simm)).unmasked()),
+    ("vfmv.v.f", ins(0b010111, OPFVF, Move(F)).unmasked()),
+    ("vmv1r.v", ins(0b100111, OPIVI, Unary).aux(0).unmasked()),
+    ("vmv2r.v", ins(0b100111, OPIVI, Unary).aux(1).unmasked()),
+    ("vmv4r.v", ins(0b100111, OPIVI, Unary).aux(3).unmasked()),
+    ("vmv8r.v", ins(0b100111, OPIVI, Unary).aux(7).unmasked()),
+    // vxor.vi vd, vs, -1
+    ("vnot.v", ins(0b001011, OPIVI, Unary).aux(0b11111)),
+    // vrsub.vx vd, vs, x0
+    ("vneg.v", ins(0b000011, OPIVX, Unary)),
+    // vwadd(u).vx vd, vs, x0
+    ("vwcvt.x.x.v", ins(0b110001, OPMVX, Unary)),
+    ("vwcvtu.x.x.v", ins(0b110000, OPMVX, Unary)),
+    // vnsrl.wx vd, vs, x0
+    ("vncvt.x.x.w", ins(0b101100, OPIVX, Unary)),
+    ("vfneg.v", ins(0b001001, OPFVV, Dup)),
+    ("vfabs.v", ins(0b001010, OPFVV, Dup)),
+    ("vmmv.m", ins(0b011001, OPMVV, Dup).unmasked()),
+    ("vmnot.m", ins(0b011101, OPMVV, Dup).unmasked()),
+    ("vmclr.m", ins(0b011011, OPMVV, Same).unmasked()),
+    ("vmset.m", ins(0b011111, OPMVV, Same).unmasked()),
+];
+
+/// Vector instruction named `name`, `None` if there is no such instruction.
+pub fn vector_ins(name: &str) -> Option<VectorIns> {
+    if let Some((_, ins)) = FIXED.iter().find(|(n, _)| *n == name) {
+        return Some(*ins);
+    }
+
+    let (base, suffix) = name.rsplit_once('.')?;
+
+    let family = |table: &[(&str, u32, Kind, &[&str])]| {
+        table
+            .iter()
+            .find(|(n, _, _, suffixes)| *n == base && suffixes.contains(&suffix))
+            .map(|(_, funct6, kind, _)| (*funct6, *kind))
+    };
+
+    if let Some((funct6, kind)) = family(INTEGER) {
+        arithmetic(funct6, kind, suffix, OPIVV, OPIVX)
+    } else if let Some((funct6, kind)) = family(MULTIPLY) {
+        arithmetic(funct6, kind, suffix, OPMVV, OPMVX)
+    } else if let Some((funct6, kind)) = family(FLOAT) {
+        arithmetic(funct6, kind, suffix, OPFVV, OPFVF)
+    } else {
+        memory_ins(name)
+    }
+}
+
+/// Form of `suffix` in a family, `vector` and `scalar` are its `funct3` for
+/// vector and scalar sources.
+fn arithmetic(
+    funct6: u32,
+    kind: Kind,
+    suffix: &str,
+    vector: u32,
+    scalar: u32,
+) -> Option<VectorIns> {
+    let (funct3, source) = match suffix {
+        "vv" | "wv" | "vs" | "mm" | "vm" | "vvm" => (vector, V),
+        "vx" | "wx" | "vxm" => (scalar, X),
+        "vf" | "wf" | "vfm" => (scalar, F),
+        "vi" | "wi" | "vim" if kind == Unsigned => (OPIVI, Uimm),
+        "vi" | "wi" | "vim" => (OPIVI, Operand::Simm),
+        _ => return None,
+    };
+
+    let form = if suffix.ends_with('m') && suffix != "mm" && suffix != "vm" {
+        VectorForm::Carry(source)
+    } else if kind == Macc {
+        VectorForm::Macc(source)
+    } else {
+        VectorForm::Binary(source)
+    };
+
+    let ins = ins(funct6, funct3, form);
+
+    if matches!(suffix, "mm" | "vm") || kind == Carry {
+        Some(ins.unmasked())
+    } else {
+        Some(ins)
+    }
+}
+
+/// Loads and stores, `vle32.v`, `vlsseg2e8.v`, `vluxei64.v`, `vl2re16.v` or `vsm.v`.
+fn memory_ins(name: &str) -> Option<VectorIns> {
+    let name = name.strip_suffix(".v")?;
+    let (opcode, rest) = match name.strip_prefix("vl") {
+        Some(rest) => (LOAD_FP, rest),
+        None => (STORE_FP, name.strip_prefix("vs")?),
+    };
+    let load = opcode == LOAD_FP;
+
+    let mem = |nf: u32, mop: u32, width: u32, form: VectorForm, aux: u32| VectorIns {
+        funct6: nf << 3 | mop,
+        funct3: width,
+        opcode,
+        form,
+        aux,
+        masked: true,
+    };
+
+    // mask load and store
+    if rest == "m" {
+        return Some(mem(0, 0b00, 0b000, VectorForm::Unit, 0b01011).unmasked());
+    }
+
+    // whole register, `vl2re32` and `vs2r`
+    if let Some(nregs) = rest.chars().next().and_then(|c| c.to_digit(10)) {
+        let rest = &rest[1..];
+        let width = match (load, rest.strip_prefix("re")) {
+            (true, Some(eew)) => element_width(eew)?,
+            (_, None) if rest == "r" => 0b000,
+            _ => return None,
+        };
+
+        if !matches!(nregs, 1 | 2 | 4 | 8) {
+            return None;
+        }
+
+        return Some(mem(nregs - 1, 0b00, width, VectorForm::Unit, 0b01000).unmasked());
+    }
+
+    let (mop, form, rest) = if let Some(rest) = rest.strip_prefix("ux") {
+        (0b01, VectorForm::Indexed, rest)
+    } else if let Some(rest) = rest.strip_prefix("ox") {
+        (0b11, VectorForm::Indexed, rest)
+    } else if rest.starts_with("seg") {
+        (0b00, VectorForm::Unit, rest)
+    } else if let Some(rest) = rest.strip_prefix('s') {
+        (0b10, VectorForm::Strided, rest)
+    } else {
+        (0b00, VectorForm::Unit, rest)
+    };
+
+    let (nf, rest) = match rest.strip_prefix("seg") {
+        Some(rest) => {
+            let nf = rest.chars().next()?.to_digit(10)?;
+
+            if !(2..=8).contains(&nf) {
+                return None;
+            }
+
+            (nf - 1, &rest[1..])
+        }
+        None => (0, rest),
+    };
+
+    let rest = match form {
+        VectorForm::Indexed => rest.strip_prefix("ei")?,
+        _ => rest.strip_prefix('e')?,
+    };
+
+    // fault only first loads
+    let (eew, lumop) = match rest.strip_suffix("ff") {
+        Some(eew) if load && form == VectorForm::Unit => (eew, 0b10000),
+        Some(_) => return None,
+        None => (rest, 0b00000),
+    };
+
+    Some(mem(nf, mop, element_width(eew)?, form, lumop))
+}
+
+/// `width` field of a load or store of `eew` bit elements.
+fn element_width(eew: &str) -> Option<u32> {
+    let width = match eew {
+        "8" => 0b000,
+        "16" => 0b101,
+        "32" => 0b110,
+        "64" => 0b111,
+        _ => return None,
+    };
+
+    Some(width)
+}
+
+pub struct VectorArgs {
+    pub funct6: u32,
+    pub vm: u32,
+    pub vs2: u32,
+    pub vs1: u32,
+    pub funct3: u32,
+    pub vd: u32,
+    pub opcode: u32,
+}
+
+pub fn vector(arg: VectorArgs) -> Vec<u8> {
+    let ins = arg.funct6 << 26
+        | arg.vm << 25
+        | arg.vs2 << 20
+        | arg.vs1 << 15
+        | arg.funct3 << 12
+        | arg.vd << 7
+        | arg.opcode;
+
+    ins.to_le_bytes().to_vec()
+}
+
+/// Encodes `ins`, `vs1` holds the register or immediate operand.
+pub fn encode_vector(
+    ins: VectorIns,
+    vd: u32,
+    vs2: u32,
+    vs1: u64,
+    vm: u32,
+) -> Result<Vec<u8>, EncodeError> {
+    // the groups start at a multiple of their size, other registers are reserved
+    if let Some(nregs) = ins.whole_registers() {
+        let regs: &[u32] = match ins.form {
+            VectorForm::Unary => &[vd, vs2],
+            _ => &[vd],
+        };
+
+        if let Some(reg) = regs.iter().find(|reg| *reg % nregs != 0) {
+            return Err(EncodeError::Register(*reg, "misaligned register group"));
+        }
+    }
+
+    let vs1 = match ins.form.source() {
+        Some(Operand::Simm) => cimm(vs1, 5, 1, true)? & 0x1F,
+        Some(Operand::Uimm) => cimm(vs1, 5, 1, false)?,
+        _ => vs1,
+    };
+
+    Ok(vector(VectorArgs {
+        funct6: ins.funct6,
+        vm,
+        vs2,
+        vs1: vs1 as u32,
+        funct3: ins.funct3,
+        vd,
+        opcode: ins.opcode,
+    }))
+}
@@ -3,11 +3,57 @@ use std::sync::atomic::Ordering;
 use colored::Colorize;
 use logos::Lexer;
 
-use crate::parser::{
-    ast::{LINE, SUCCESS},
-    token::Token,
+use crate::{
+    elf::attributes::attribute_tag,
+    parser::{
+        ast::{LINE, SUCCESS},
+        token::Token,
+    },
+    riscv::{
+        csr::{csr_extension, csr_number, csr_xlen},
+        insn::opcode_number,
+        isa::{
+            Extension, also_required_extension, is_embedded, is_enabled, required_extension,
+            required_xlen, xlen,
+        },
+    },
 };
 
+pub fn check_extension(token: &Token, lex: &mut Lexer<'_, Token>) {
+    let exts = required_extension(token);
+
+    if !exts.is_empty() && !exts.iter().any(|ext| is_enabled(*ext)) {
+        missing_extension(exts, lex);
+    }
+
+    if let Some(ext) = also_required_extension(token).filter(|ext| !is_enabled(*ext)) {
+        missing_extension(&[ext], lex);
+    }
+
+    if let Some(required) = required_xlen(token).filter(|required| *required != xlen()) {
+        SUCCESS.store(false, Ordering::SeqCst);
+
+        println!(
+            "{}\n\tLine: {}",
+            format!("Error, `{}` requires RV{required}", lex.slice()).bright_red(),
+            LINE.load(Ordering::Relaxed)
+        );
+    }
+}
+
+fn missing_extension(exts: &[Extension], lex: &Lexer<'_, Token>) {
+    SUCCESS.store(false, Ordering::SeqCst);
+
+    let names = exts.iter().map(|ext| ext.name()).collect::<Vec<_>>();
+
+    println!(
+        "{}\n\tLine: {}",
+        format!("Error, `{}` requires extension {}", lex.slice(), names.join(" or "))
+            .bright_red(),
+        LINE.load(Ordering::Relaxed)
+    );
+}
+
 pub fn check_num(reg: &Token, lex: &mut Lexer<'_, Token>) -> u64 {
     match reg {
         Token::Number(n) => *n,
@@ -35,7 +81,37 @@ pub fn check_num(reg: &Token, lex: &mut Lexer<'_, Token>) -> u64 {
 }
 
 pub fn token_to_reg(token: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
-    match token {
+    match reg_number(token) {
+        Some(reg) if reg >= 16 && is_embedded() => {
+            SUCCESS.store(false, Ordering::SeqCst);
+
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Error, the E base ISA only has x0-x15:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            reg
+        }
+        Some(reg) => reg,
+        None => {
+            SUCCESS.store(false, Ordering::SeqCst);
+
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Syntax Error, Expected Reg:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            0
+        }
+    }
+}
+
+pub fn reg_number(token: &Token) -> Option<u32> {
+    let reg = match token {
         Token::Zero | Token::X0 => 0,
         Token::X1 | Token::Ra => 1,
         Token::X2 | Token::Sp => 2,
@@ -69,12 +145,356 @@ pub fn token_to_reg(token: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
         Token::X30 | Token::T5 => 30,
         Token::X31 | Token::T6 => 31,
 
+        _ => return None,
+    };
+
+    Some(reg)
+}
+
+pub fn token_to_freg(token: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
+    match freg_number(token) {
+        Some(reg) => reg,
+        None => {
+            SUCCESS.store(false, Ordering::SeqCst);
+
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Syntax Error, Expected Float Reg:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            0
+        }
+    }
+}
+
+pub fn freg_number(token: &Token) -> Option<u32> {
+    let reg = match token {
+        Token::F0 | Token::Ft0 => 0,
+        Token::F1 | Token::Ft1 => 1,
+        Token::F2 | Token::Ft2 => 2,
+        Token::F3 | Token::Ft3 => 3,
+        Token::F4 | Token::Ft4 => 4,
+        Token::F5 | Token::Ft5 => 5,
+        Token::F6 | Token::Ft6 => 6,
+        Token::F7 | Token::Ft7 => 7,
+        Token::F8 | Token::Fs0 => 8,
+        Token::F9 | Token::Fs1 => 9,
+        Token::F10 | Token::Fa0 => 10,
+        Token::F11 | Token::Fa1 => 11,
+        Token::F12 | Token::Fa2 => 12,
+        Token::F13 | Token::Fa3 => 13,
+        Token::F14 | Token::Fa4 => 14,
+        Token::F15 | Token::Fa5 => 15,
+        Token::F16 | Token::Fa6 => 16,
+        Token::F17 | Token::Fa7 => 17,
+        Token::F18 | Token::Fs2 => 18,
+        Token::F19 | Token::Fs3 => 19,
+        Token::F20 | Token::Fs4 => 20,
+        Token::F21 | Token::Fs5 => 21,
+        Token::F22 | Token::Fs6 => 22,
+        Token::F23 | Token::Fs7 => 23,
+        Token::F24 | Token::Fs8 => 24,
+        Token::F25 | Token::Fs9 => 25,
+        Token::F26 | Token::Fs10 => 26,
+        Token::F27 | Token::Fs11 => 27,
+        Token::F28 | Token::Ft8 => 28,
+        Token::F29 | Token::Ft9 => 29,
+        Token::F30 | Token::Ft10 => 30,
+        Token::F31 | Token::Ft11 => 31,
+
+        _ => return None,
+    };
+
+    Some(reg)
+}
+
+pub fn token_to_vreg(token: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
+    match vreg_number(token) {
+        Some(reg) => reg,
+        None => {
+            SUCCESS.store(false, Ordering::SeqCst);
+
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Syntax Error, Expected Vector Reg:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            0
+        }
+    }
+}
+
+pub fn vreg_number(token: &Token) -> Option<u32> {
+    let reg = match token {
+        Token::V0 => 0,
+        Token::V1 => 1,
+        Token::V2 => 2,
+        Token::V3 => 3,
+        Token::V4 => 4,
+        Token::V5 => 5,
+        Token::V6 => 6,
+        Token::V7 => 7,
+        Token::V8 => 8,
+        Token::V9 => 9,
+        Token::V10 => 10,
+        Token::V11 => 11,
+        Token::V12 => 12,
+        Token::V13 => 13,
+        Token::V14 => 14,
+        Token::V15 => 15,
+        Token::V16 => 16,
+        Token::V17 => 17,
+        Token::V18 => 18,
+        Token::V19 => 19,
+        Token::V20 => 20,
+        Token::V21 => 21,
+        Token::V22 => 22,
+        Token::V23 => 23,
+        Token::V24 => 24,
+        Token::V25 => 25,
+        Token::V26 => 26,
+        Token::V27 => 27,
+        Token::V28 => 28,
+        Token::V29 => 29,
+        Token::V30 => 30,
+        Token::V31 => 31,
+
+        _ => return None,
+    };
+
+    Some(reg)
+}
+
+/// `vsew` and `vlmul` bits of the `e32, m1` vtype operands.
+pub fn token_to_vtype(sew: &Token, lmul: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
+    let vsew = match sew {
+        Token::Identifier(s) => match s.as_str() {
+            "e8" => Some(0b000),
+            "e16" => Some(0b001),
+            "e32" => Some(0b010),
+            "e64" => Some(0b011),
+            _ => None,
+        },
+        _ => None,
+    };
+    let vlmul = match lmul {
+        Token::Identifier(s) => match s.as_str() {
+            "m1" => Some(0b000),
+            "m2" => Some(0b001),
+            "m4" => Some(0b010),
+            "m8" => Some(0b011),
+            "mf8" => Some(0b101),
+            "mf4" => Some(0b110),
+            "mf2" => Some(0b111),
+            _ => None,
+        },
+        _ => None,
+    };
+
+    match (vsew, vlmul) {
+        (Some(vsew), Some(vlmul)) => vsew << 3 | vlmul,
         _ => {
             SUCCESS.store(false, Ordering::SeqCst);
 
             println!(
                 "{}\n\tFound: {}\n\tLine: {}",
-                "Syntax Error, Expected Reg:".bright_red(),
+                "Syntax Error, Expected vtype:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            0
+        }
+    }
+}
+
+/// vtype bit of a tail or mask policy, `ta`, `tu`, `ma` or `mu`.
+pub fn vtype_policy(token: &Token) -> Option<u32> {
+    let bit = match token {
+        Token::Identifier(s) => match s.as_str() {
+            "ta" => 1 << 6,
+            "ma" => 1 << 7,
+            "tu" | "mu" => 0,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    Some(bit)
+}
+
+/// Values loaded by `fli`, by their encoding. `min` and `nan` are only
+/// accepted by name.
+const FLI_VALUES: [f64; 32] = [
+    -1.0,
+    f64::NAN,
+    1.52587890625e-05,
+    3.0517578125e-05,
+    0.00390625,
+    0.0078125,
+    0.0625,
+    0.125,
+    0.25,
+    0.3125,
+    0.375,
+    0.4375,
+    0.5,
+    0.625,
+    0.75,
+    0.875,
+    1.0,
+    1.25,
+    1.5,
+    1.75,
+    2.0,
+    2.5,
+    3.0,
+    4.0,
+    8.0,
+    16.0,
+    128.0,
+    256.0,
+    32768.0,
+    65536.0,
+    f64::INFINITY,
+    f64::NAN,
+];
+
+/// Encoding of the constant operand of `fli`.
+pub fn fli_index(token: &Token) -> Option<u32> {
+    let value = match token {
+        // `min` is lexed as the instruction
+        Token::Min => return Some(1),
+        Token::Identifier(s) if s == "inf" => return Some(30),
+        Token::Identifier(s) if s == "nan" => return Some(31),
+        Token::Float(s) => s.parse::<f64>().ok()?,
+        Token::Number(n) => *n as f64,
+        Token::NegNumber(n) => *n as f64,
+        _ => return None,
+    };
+
+    FLI_VALUES
+        .iter()
+        .position(|v| *v == value)
+        .map(|i| i as u32)
+}
+
+pub fn token_to_fli(token: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
+    match fli_index(token) {
+        Some(index) => index,
+        None => {
+            SUCCESS.store(false, Ordering::SeqCst);
+
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Syntax Error, Expected fli constant:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            0
+        }
+    }
+}
+
+/// Encoding of a rounding mode operand.
+pub fn rounding_mode(token: &Token) -> Option<u32> {
+    let rm = match token {
+        Token::Rne => 0b000,
+        Token::Rtz => 0b001,
+        Token::Rdn => 0b010,
+        Token::Rup => 0b011,
+        Token::Rmm => 0b100,
+        Token::Dyn => 0b111,
+
+        _ => return None,
+    };
+
+    Some(rm)
+}
+
+/// CSR operand, either its number or its name.
+pub fn token_to_csr(token: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
+    let csr = match token {
+        Token::Number(n) if *n <= 0xFFF => Some(*n as u32),
+        Token::Identifier(name) => {
+            if let Some(ext) = csr_extension(name).filter(|ext| !is_enabled(*ext)) {
+                SUCCESS.store(false, Ordering::SeqCst);
+
+                println!(
+                    "{}\n\tLine: {}",
+                    format!("Error, `{name}` requires extension {}", ext.name()).bright_red(),
+                    LINE.load(Ordering::Relaxed)
+                );
+            }
+
+            if let Some(required) = csr_xlen(name).filter(|required| *required != xlen()) {
+                SUCCESS.store(false, Ordering::SeqCst);
+
+                println!(
+                    "{}\n\tLine: {}",
+                    format!("Error, `{name}` requires RV{required}").bright_red(),
+                    LINE.load(Ordering::Relaxed)
+                );
+            }
+
+            csr_number(name)
+        }
+        _ => None,
+    };
+
+    match csr {
+        Some(csr) => csr,
+        None => {
+            SUCCESS.store(false, Ordering::SeqCst);
+
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Syntax Error, Expected CSR:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            0
+        }
+    }
+}
+
+/// Bits of a fence predecessor or successor set, `rw` or `iorw`.
+pub fn fence_set(token: &Token) -> Option<u32> {
+    let set = match token {
+        Token::Identifier(s) => s.as_str(),
+        // `or` is lexed as the instruction
+        Token::Or => "or",
+        _ => return None,
+    };
+
+    let mut bits = 0;
+    let mut rest = set;
+
+    for (c, bit) in [('i', 0b1000), ('o', 0b0100), ('r', 0b0010), ('w', 0b0001)] {
+        if let Some(r) = rest.strip_prefix(c) {
+            bits |= bit;
+            rest = r;
+        }
+    }
+
+    (rest.is_empty() && bits != 0).then_some(bits)
+}
+
+pub fn token_to_fence_set(token: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
+    match fence_set(token) {
+        Some(set) => set,
+        None => {
+            SUCCESS.store(false, Ordering::SeqCst);
+
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Syntax Error, Expected fence set:".bright_red(),
                 lex.slice(),
                 LINE.load(Ordering::Relaxed)
             );
@@ -119,6 +539,64 @@ pub fn token_to_identifier(token: &Token, lex: &mut Lexer<'_, Token>) -> String
     }
 }
 
+/// Tag of `.attribute`, a number or a name like `arch`.
+pub fn token_to_attribute_tag(token: &Token, lex: &mut Lexer<'_, Token>) -> u64 {
+    let tag = match token {
+        Token::Number(n) => Some(*n),
+        Token::Identifier(name) => attribute_tag(name),
+        _ => None,
+    };
+
+    match tag {
+        Some(tag) => tag,
+        None => {
+            SUCCESS.store(false, Ordering::SeqCst);
+
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Syntax Error, Expected attribute tag:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            0
+        }
+    }
+}
+
+/// Major opcode of `.insn`, a number or a name like `CUSTOM_0`.
+pub fn token_to_opcode(token: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
+    let opcode = match token {
+        Token::Number(n) => u32::try_from(*n).ok(),
+        Token::Identifier(name) => opcode_number(name),
+        _ => None,
+    };
+
+    match opcode {
+        Some(opcode) => opcode,
+        None => {
+            SUCCESS.store(false, Ordering::SeqCst);
+
+            println!(
+                "{}\n\tFound: {}\n\tLine: {}",
+                "Syntax Error, Expected opcode:".bright_red(),
+                lex.slice(),
+                LINE.load(Ordering::Relaxed)
+            );
+
+            0
+        }
+    }
+}
+
+/// Register operand of `.insn`, which takes integer and float registers alike.
+pub fn token_to_insn_reg(token: &Token, lex: &mut Lexer<'_, Token>) -> u32 {
+    match freg_number(token) {
+        Some(reg) => reg,
+        None => token_to_reg(token, lex),
+    }
+}
+
 pub fn token_to_string(token: &Token, lex: &mut Lexer<'_, Token>) -> String {
     match token {
         Token::Str(s) => s.to_string(),